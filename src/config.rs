@@ -1,7 +1,15 @@
 use std::env;
+use std::fmt;
 use std::{ slice::Iter, iter::Skip };
 use std::time::Duration;
+use std::path::Path;
+use std::net::IpAddr;
 use url::Url;
+use isahc::http::{ Method, HeaderName, HeaderValue };
+use serde::Deserialize;
+use rand::{ SeedableRng, rngs::StdRng };
+
+const FD_HEADROOM: u64 = 64; // file descriptors needed besides the sockets (stdio, isahc internals, etc)
 
 // Error messages
 const ERR_INVALID_REQUESTS: &str = "Invalid number of requests\nUse --help for more info";
@@ -12,11 +20,343 @@ const ERR_INVALID_DURATION: &str = "Invalid value for duration\nUse --help for m
 const ERR_INVALID_TIMEOUT: &str = "Invalid value for timeout\nUse --help for more info";
 const ERR_INVALID_CONNECTION_TIMEOUT: &str =
     "Invalid value for connection-timeout\nUse --help for more info";
+const ERR_INVALID_CERT_EXPIRY: &str =
+    "Invalid value for check-cert-expiry\nUse --help for more info";
+const ERR_INVALID_RECORD: &str = "Invalid path for record\nUse --help for more info";
+const ERR_INVALID_OUT_FILE: &str = "Invalid path for out-file\nUse --help for more info";
+const ERR_INVALID_TIMELINE: &str = "Invalid path for timeline\nUse --help for more info";
+const ERR_INVALID_RAW_CSV: &str = "Invalid path for raw-csv\nUse --help for more info";
+const ERR_INVALID_MAX_BLOCKING_THREADS: &str =
+    "Invalid value for max-blocking-threads\nUse --help for more info";
+const ERR_INVALID_CONNECTIONS: &str = "Invalid number of connections\nUse --help for more info";
+const ERR_INVALID_PIPELINE: &str = "Invalid pipeline depth\nUse --help for more info";
+const ERR_INVALID_RATE: &str = "Invalid rate\nUse --help for more info";
+const ERR_CORRECT_CO_WITHOUT_RATE: &str = "--correct-co requires --rate\nUse --help for more info";
+const ERR_INVALID_STAGE: &str =
+    "Invalid stage, expected \"<duration>:c=<concurrency>\" (e.g 60s:c=10)\nUse --help for more info";
+const ERR_STAGE_CONFLICT: &str =
+    "--stage cannot be combined with -n, -d, -c, --pipeline or --cache-comparison\nUse --help for more info";
+const ERR_INVALID_RAMP: &str =
+    "Invalid ramp, expected \"<start>:<end>:<duration>\" (e.g 0:1000:30s)\nUse --help for more info";
+const ERR_RAMP_CONFLICT: &str =
+    "--ramp requires -d and cannot be combined with -c, --pipeline or --stage\nUse --help for more info";
+const ERR_INVALID_MAX_DURATION: &str = "Invalid value for max-duration\nUse --help for more info";
+const ERR_MAX_DURATION_CONFLICT: &str =
+    "--max-duration caps a request count test, it cannot be combined with -d or --stage\nUse --help for more info";
+const ERR_INVALID_MAX_REDIRECTS: &str = "Invalid value for max-redirects\nUse --help for more info";
+const ERR_INVALID_WARMUP: &str = "Invalid warmup\nUse --help for more info";
+const ERR_INVALID_SEED: &str = "Invalid seed\nUse --help for more info";
+const ERR_INVALID_VERIFY: &str = "Invalid value for verify, expected the path of a json report\nUse --help for more info";
+const ERR_INVALID_NAME: &str = "Invalid name, expected a non-empty label for the run\nUse --help for more info";
+const ERR_INVALID_THINK_TIME: &str =
+    "Invalid think-time, expected a duration or a range (100ms-500ms)\nUse --help for more info";
+const ERR_PIPELINE_WITHOUT_CONNECTIONS: &str =
+    "--pipeline requires --connections\nUse --help for more info";
+const ERR_CONTRADICTING_CONCURRENCY: &str =
+    "Concurrency must equal connections x pipeline depth\nUse --help for more info";
+const ERR_INVALID_RETRIES: &str = "Invalid value for retries\nUse --help for more info";
+const ERR_INVALID_RETRY_BACKOFF: &str = "Invalid value for retry-backoff\nUse --help for more info";
+const ERR_RETRY_ON_5XX_WITHOUT_RETRIES: &str = "--retry-on-5xx requires --retries\nUse --help for more info";
+const ERR_EXPECT_CONTINUE_WITHOUT_BODY: &str = "--expect-continue needs a request body\nUse --help for more info";
+const ERR_INVALID_MAX_ERRORS: &str = "Invalid value for max-errors\nUse --help for more info";
+const ERR_INVALID_MAX_ERROR_RATE: &str =
+    "Invalid value for max-error-rate (0 <= PCT < 100)\nUse --help for more info";
+const ERR_INVALID_FAIL_IF: &str =
+    "Invalid fail-if condition, expected e.g \"p99>200ms\" or \"error_rate>1%\"\nUse --help for more info";
+const ERR_INVALID_MIN_SAMPLES: &str = "Invalid value for min-samples\nUse --help for more info";
+const ERR_HUMAN_STDERR_NEEDS_MACHINE_OUTPUT: &str =
+    "--human-stderr needs a machine readable -o (json, prometheus, openmetrics or markdown) on stdout\nUse --help for more info";
+const ERR_INVALID_BUCKETS: &str = "Invalid number of buckets\nUse --help for more info";
+const ERR_INVALID_HIST_MAX: &str = "Invalid value for hist-max\nUse --help for more info";
+const ERR_TOKIO_CONSOLE_UNAVAILABLE: &str =
+    "--tokio-console needs a build with the tokio-console feature\nUse --help for more info";
+const ERR_INVALID_SLA: &str = "Invalid value for sla\nUse --help for more info";
+const ERR_INVALID_PERCENTILES: &str =
+    "Percentiles must be a comma separated list of values within 0..=100\nUse --help for more info";
+const ERR_INVALID_CONFIRM_REQUESTS: &str =
+    "Invalid value for confirm-requests\nUse --help for more info";
+const ERR_INVALID_CONFIRM_CONCURRENCY: &str =
+    "Invalid value for confirm-concurrency\nUse --help for more info";
+const ERR_INVALID_RESTRICT_REDIRECTS: &str =
+    "Invalid redirect restriction (same-host, same-domain, any)\nUse --help for more info";
+const ERR_INVALID_HTTP_VERSION: &str = "Invalid HTTP version (1.1, 2)\nUse --help for more info";
+const ERR_INVALID_RESOLVE: &str =
+    "Invalid resolve, expected \"<host>:<port>:<address>\" (e.g example.com:443:10.0.0.5)\nUse --help for more info";
+const ERR_INVALID_LATENCY_UNIT: &str = "Invalid latency unit\nUse --help for more info";
+const ERR_INVALID_OUTPUT: &str = "Invalid output format\nUse --help for more info";
+const ERR_INVALID_GRAPHQL: &str = "Invalid GraphQL query\nUse --help for more info";
+const ERR_INVALID_GRAPHQL_FILE: &str = "Failed to read GraphQL file\nUse --help for more info";
+const ERR_INVALID_GRAPHQL_VARIABLES: &str =
+    "GraphQL variables must be a JSON object\nUse --help for more info";
+const ERR_GRAPHQL_QUERY_TWICE: &str =
+    "--graphql and --graphql-file are mutually exclusive\nUse --help for more info";
+const ERR_INVALID_ADAPTIVE_TIMEOUT: &str =
+    "Invalid multiplier for adaptive-timeout\nUse --help for more info";
+const ERR_INVALID_METHOD: &str =
+    "Invalid HTTP method (GET, POST, PUT, DELETE, PATCH, HEAD, OPTIONS)\nUse --help for more info";
+const ERR_INVALID_HEADER: &str =
+    "Invalid header, expected \"Name: Value\"\nUse --help for more info";
+const ERR_INVALID_BASIC_AUTH: &str =
+    "Invalid basic auth, expected \"user:pass\"\nUse --help for more info";
+const ERR_INVALID_BEARER: &str = "Invalid bearer token\nUse --help for more info";
+const ERR_INVALID_REQUEST_ID_HEADER: &str = "Invalid request id header name\nUse --help for more info";
+const ERR_AUTH_TWICE: &str =
+    "--basic-auth and --bearer are mutually exclusive\nUse --help for more info";
+const ERR_INVALID_BODY: &str = "Invalid request body\nUse --help for more info";
+const ERR_INVALID_BODY_FILE: &str = "Failed to read body file\nUse --help for more info";
+const ERR_INVALID_COOKIE: &str = "Invalid cookie, expected \"name=value\"\nUse --help for more info";
+const ERR_BODY_TWICE: &str =
+    "--body and --body-file are mutually exclusive\nUse --help for more info";
+const ERR_BODY_WITH_GRAPHQL: &str =
+    "--body/--body-file cannot be combined with --graphql\nUse --help for more info";
+const ERR_INVALID_EXPECT_STATUS: &str =
+    "Invalid expected status, expected codes like 200,204\nUse --help for more info";
+const ERR_INVALID_EXPECT_SUBSTRING: &str = "Invalid expected substring\nUse --help for more info";
+const ERR_INVALID_FORM: &str = "Invalid form field, expected \"key=value\"\nUse --help for more info";
+const ERR_INVALID_FORM_FILE: &str =
+    "Invalid form file, expected \"field=@path\" of a readable file\nUse --help for more info";
+const ERR_FORM_WITH_BODY: &str =
+    "--form/--form-file cannot be combined with --body/--body-file or --graphql\nUse --help for more info";
+const ERR_UNSET_ENV_VAR: &str =
+    "Environment variable referenced in a header or body is not set\nUse --help for more info";
+const ERR_INVALID_CONFIG_FILE: &str = "Failed to read config file\nUse --help for more info";
+const ERR_INVALID_CONFIG: &str = "Invalid config file\nUse --help for more info";
 const ERR_URL_NOT_PROVIDED: &str = "URL not provided\nUse --help for more info";
 const ERR_INVALID_URL: &str = "Invalid URL\nUse --help for more info";
+const ERR_UNSUPPORTED_SCHEME: &str = "Unsupported URL scheme, only http and https are supported\nUse --help for more info";
+const ERR_INVALID_URLS_FILE: &str = "Failed to read URLs file\nUse --help for more info";
+const ERR_EMPTY_URLS_FILE: &str = "URLs file contains no URLs\nUse --help for more info";
+const ERR_INVALID_URLS_SAMPLE: &str = "Invalid value for urls-sample\nUse --help for more info";
+const ERR_URLS_SAMPLE_WITHOUT_FILE: &str = "--urls-sample needs --urls-file\nUse --help for more info";
+const ERR_INVALID_SCRIPT: &str = "Failed to read request script\nUse --help for more info";
+const ERR_INVALID_SCRIPT_LINE: &str =
+    "Invalid request script line, expected [WEIGHT] METHOD URL [BODY-FILE]\nUse --help for more info";
+const ERR_EMPTY_SCRIPT: &str = "Request script contains no requests\nUse --help for more info";
 const ERR_INVALID_REQUESTS_AND_CONCURRENCY: &str =
     "Number of requests must be >= concurrency\nUse --help for more info";
 
+// Why the options could not be parsed, the String is the offending input (empty when it was missing)
+#[derive(Debug, PartialEq, Clone)]
+pub enum ConfigError {
+    Help, // -h / --help (or no arguments), not a failure
+    Version, // -v / --version, not a failure
+    UnknownArgument(String),
+    InvalidRequests(String),
+    InvalidConcurrency(String),
+    InvalidThreads(String),
+    InvalidDuration(String),
+    InvalidTimeout(String),
+    InvalidConnectionTimeout(String),
+    InvalidCertExpiry(String),
+    InvalidRecord(String),
+    InvalidOutFile(String),
+    InvalidTimeline(String),
+    InvalidRawCsv(String),
+    InvalidMaxBlockingThreads(String),
+    InvalidConnections(String),
+    InvalidPipeline(String),
+    InvalidRate(String),
+    CorrectCoWithoutRate,
+    InvalidRetries(String),
+    InvalidRetryBackoff(String),
+    RetryOn5xxWithoutRetries,
+    ExpectContinueWithoutBody,
+    InvalidStage(String),
+    StageConflict,
+    InvalidRamp(String),
+    RampConflict,
+    InvalidMaxDuration(String),
+    MaxDurationConflict,
+    InvalidMaxRedirects(String),
+    InvalidWarmup(String),
+    InvalidSeed(String),
+    InvalidName(String),
+    InvalidVerify(String),
+    InvalidThinkTime(String),
+    PipelineWithoutConnections,
+    ContradictingConcurrency {
+        concurrency: usize,
+        connections: usize,
+        pipeline: usize,
+    },
+    InvalidMaxErrors(String),
+    InvalidMaxErrorRate(String),
+    InvalidFailIf(String),
+    InvalidMinSamples(String),
+    HumanStderrNeedsMachineOutput,
+    InvalidBuckets(String),
+    InvalidHistMax(String),
+    InvalidSla(String),
+    TokioConsoleUnavailable,
+    InvalidPercentiles(String),
+    InvalidConfirmRequests(String),
+    InvalidConfirmConcurrency(String),
+    InvalidHttpVersion(String),
+    InvalidRestrictRedirects(String),
+    InvalidResolve(String),
+    InvalidLatencyUnit(String),
+    InvalidOutput(String),
+    InvalidGraphql(String),
+    InvalidGraphqlFile(String),
+    InvalidGraphqlVariables(String),
+    GraphqlQueryTwice,
+    InvalidAdaptiveTimeout(String),
+    InvalidMethod(String),
+    InvalidHeader(String),
+    InvalidBasicAuth(String),
+    InvalidBearer(String),
+    InvalidRequestIdHeader(String),
+    AuthTwice,
+    InvalidCookie(String),
+    InvalidBody(String),
+    InvalidBodyFile(String),
+    BodyTwice,
+    BodyWithGraphql,
+    InvalidExpectStatus(String),
+    InvalidExpectSubstring(String),
+    InvalidForm(String),
+    InvalidFormFile(String),
+    FormWithBody,
+    UnsetEnvVar(String),
+    InvalidConfigFile(String),
+    InvalidConfig {
+        path: String,
+        reason: String, // from the TOML parser, names the offending key
+    },
+    UrlNotProvided,
+    InvalidUrl(String),
+    UnsupportedScheme(String),
+    InvalidUrlsFile(String),
+    EmptyUrlsFile(String),
+    InvalidUrlsSample(String),
+    UrlsSampleWithoutFile,
+    InvalidScript(String),
+    InvalidScriptLine(String),
+    EmptyScript(String),
+    InvalidRequestsAndConcurrency,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (value, message) = match self {
+            ConfigError::Help => {
+                return write!(f, "Help requested");
+            }
+            ConfigError::Version => {
+                return write!(f, "{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+            }
+            ConfigError::UnknownArgument(arg) => {
+                return write!(f, "Unknown argument \"{}\"\nUse --help for more info", arg);
+            }
+            ConfigError::ContradictingConcurrency { concurrency, connections, pipeline } => {
+                return write!(
+                    f,
+                    "-c {} but {} connection(s) x {} pipeline depth = {}\n{}",
+                    concurrency,
+                    connections,
+                    pipeline,
+                    connections * pipeline,
+                    ERR_CONTRADICTING_CONCURRENCY
+                );
+            }
+            ConfigError::InvalidConfig { path, reason } => {
+                return write!(f, "\"{}\"\n{}\n{}", path, reason, ERR_INVALID_CONFIG);
+            }
+            ConfigError::InvalidRequests(value) => (value.as_str(), ERR_INVALID_REQUESTS),
+            ConfigError::InvalidConcurrency(value) => (value.as_str(), ERR_INVALID_CONCURRENCY),
+            ConfigError::InvalidThreads(value) => (value.as_str(), ERR_INVALID_THREADS),
+            ConfigError::InvalidDuration(value) => (value.as_str(), ERR_INVALID_DURATION),
+            ConfigError::InvalidTimeout(value) => (value.as_str(), ERR_INVALID_TIMEOUT),
+            ConfigError::InvalidConnectionTimeout(value) => (value.as_str(), ERR_INVALID_CONNECTION_TIMEOUT),
+            ConfigError::InvalidCertExpiry(value) => (value.as_str(), ERR_INVALID_CERT_EXPIRY),
+            ConfigError::InvalidRecord(value) => (value.as_str(), ERR_INVALID_RECORD),
+            ConfigError::InvalidOutFile(value) => (value.as_str(), ERR_INVALID_OUT_FILE),
+            ConfigError::InvalidTimeline(value) => (value.as_str(), ERR_INVALID_TIMELINE),
+            ConfigError::InvalidRawCsv(value) => (value.as_str(), ERR_INVALID_RAW_CSV),
+            ConfigError::InvalidMaxBlockingThreads(value) => (value.as_str(), ERR_INVALID_MAX_BLOCKING_THREADS),
+            ConfigError::InvalidConnections(value) => (value.as_str(), ERR_INVALID_CONNECTIONS),
+            ConfigError::InvalidPipeline(value) => (value.as_str(), ERR_INVALID_PIPELINE),
+            ConfigError::InvalidRate(value) => (value.as_str(), ERR_INVALID_RATE),
+            ConfigError::InvalidStage(value) => (value.as_str(), ERR_INVALID_STAGE),
+            ConfigError::InvalidMaxRedirects(value) => (value.as_str(), ERR_INVALID_MAX_REDIRECTS),
+            ConfigError::InvalidWarmup(value) => (value.as_str(), ERR_INVALID_WARMUP),
+            ConfigError::InvalidSeed(value) => (value.as_str(), ERR_INVALID_SEED),
+            ConfigError::InvalidName(value) => (value.as_str(), ERR_INVALID_NAME),
+            ConfigError::InvalidVerify(value) => (value.as_str(), ERR_INVALID_VERIFY),
+            ConfigError::InvalidThinkTime(value) => (value.as_str(), ERR_INVALID_THINK_TIME),
+            ConfigError::InvalidMaxErrors(value) => (value.as_str(), ERR_INVALID_MAX_ERRORS),
+            ConfigError::InvalidMaxErrorRate(value) => (value.as_str(), ERR_INVALID_MAX_ERROR_RATE),
+            ConfigError::InvalidFailIf(value) => (value.as_str(), ERR_INVALID_FAIL_IF),
+            ConfigError::InvalidMinSamples(value) => (value.as_str(), ERR_INVALID_MIN_SAMPLES),
+            ConfigError::HumanStderrNeedsMachineOutput => ("", ERR_HUMAN_STDERR_NEEDS_MACHINE_OUTPUT),
+            ConfigError::InvalidBuckets(value) => (value.as_str(), ERR_INVALID_BUCKETS),
+            ConfigError::InvalidHistMax(value) => (value.as_str(), ERR_INVALID_HIST_MAX),
+            ConfigError::InvalidSla(value) => (value.as_str(), ERR_INVALID_SLA),
+            ConfigError::TokioConsoleUnavailable => ("", ERR_TOKIO_CONSOLE_UNAVAILABLE),
+            ConfigError::InvalidPercentiles(value) => (value.as_str(), ERR_INVALID_PERCENTILES),
+            ConfigError::InvalidConfirmRequests(value) => (value.as_str(), ERR_INVALID_CONFIRM_REQUESTS),
+            ConfigError::InvalidConfirmConcurrency(value) => (value.as_str(), ERR_INVALID_CONFIRM_CONCURRENCY),
+            ConfigError::InvalidHttpVersion(value) => (value.as_str(), ERR_INVALID_HTTP_VERSION),
+            ConfigError::InvalidRestrictRedirects(value) => (value.as_str(), ERR_INVALID_RESTRICT_REDIRECTS),
+            ConfigError::InvalidResolve(value) => (value.as_str(), ERR_INVALID_RESOLVE),
+            ConfigError::InvalidLatencyUnit(value) => (value.as_str(), ERR_INVALID_LATENCY_UNIT),
+            ConfigError::InvalidOutput(value) => (value.as_str(), ERR_INVALID_OUTPUT),
+            ConfigError::InvalidGraphql(value) => (value.as_str(), ERR_INVALID_GRAPHQL),
+            ConfigError::InvalidGraphqlFile(value) => (value.as_str(), ERR_INVALID_GRAPHQL_FILE),
+            ConfigError::InvalidGraphqlVariables(value) => (value.as_str(), ERR_INVALID_GRAPHQL_VARIABLES),
+            ConfigError::InvalidAdaptiveTimeout(value) => (value.as_str(), ERR_INVALID_ADAPTIVE_TIMEOUT),
+            ConfigError::InvalidMethod(value) => (value.as_str(), ERR_INVALID_METHOD),
+            ConfigError::InvalidHeader(value) => (value.as_str(), ERR_INVALID_HEADER),
+            ConfigError::InvalidBasicAuth(value) => (value.as_str(), ERR_INVALID_BASIC_AUTH),
+            ConfigError::InvalidBearer(value) => (value.as_str(), ERR_INVALID_BEARER),
+            ConfigError::InvalidRequestIdHeader(value) => (value.as_str(), ERR_INVALID_REQUEST_ID_HEADER),
+            ConfigError::InvalidCookie(value) => (value.as_str(), ERR_INVALID_COOKIE),
+            ConfigError::InvalidBody(value) => (value.as_str(), ERR_INVALID_BODY),
+            ConfigError::InvalidBodyFile(value) => (value.as_str(), ERR_INVALID_BODY_FILE),
+            ConfigError::InvalidExpectStatus(value) => (value.as_str(), ERR_INVALID_EXPECT_STATUS),
+            ConfigError::InvalidExpectSubstring(value) => (value.as_str(), ERR_INVALID_EXPECT_SUBSTRING),
+            ConfigError::InvalidForm(value) => (value.as_str(), ERR_INVALID_FORM),
+            ConfigError::InvalidFormFile(value) => (value.as_str(), ERR_INVALID_FORM_FILE),
+            ConfigError::UnsetEnvVar(value) => (value.as_str(), ERR_UNSET_ENV_VAR),
+            ConfigError::InvalidConfigFile(value) => (value.as_str(), ERR_INVALID_CONFIG_FILE),
+            ConfigError::InvalidUrl(value) => (value.as_str(), ERR_INVALID_URL),
+            ConfigError::UnsupportedScheme(value) => (value.as_str(), ERR_UNSUPPORTED_SCHEME),
+            ConfigError::InvalidUrlsFile(value) => (value.as_str(), ERR_INVALID_URLS_FILE),
+            ConfigError::EmptyUrlsFile(value) => (value.as_str(), ERR_EMPTY_URLS_FILE),
+            ConfigError::InvalidUrlsSample(value) => (value.as_str(), ERR_INVALID_URLS_SAMPLE),
+            ConfigError::UrlsSampleWithoutFile => ("", ERR_URLS_SAMPLE_WITHOUT_FILE),
+            ConfigError::InvalidScript(value) => (value.as_str(), ERR_INVALID_SCRIPT),
+            ConfigError::InvalidScriptLine(value) => (value.as_str(), ERR_INVALID_SCRIPT_LINE),
+            ConfigError::EmptyScript(value) => (value.as_str(), ERR_EMPTY_SCRIPT),
+            ConfigError::CorrectCoWithoutRate => ("", ERR_CORRECT_CO_WITHOUT_RATE),
+            ConfigError::InvalidRetries(value) => (value.as_str(), ERR_INVALID_RETRIES),
+            ConfigError::InvalidRetryBackoff(value) => (value.as_str(), ERR_INVALID_RETRY_BACKOFF),
+            ConfigError::RetryOn5xxWithoutRetries => ("", ERR_RETRY_ON_5XX_WITHOUT_RETRIES),
+            ConfigError::ExpectContinueWithoutBody => ("", ERR_EXPECT_CONTINUE_WITHOUT_BODY),
+            ConfigError::StageConflict => ("", ERR_STAGE_CONFLICT),
+            ConfigError::InvalidRamp(value) => (value.as_str(), ERR_INVALID_RAMP),
+            ConfigError::RampConflict => ("", ERR_RAMP_CONFLICT),
+            ConfigError::InvalidMaxDuration(value) => (value.as_str(), ERR_INVALID_MAX_DURATION),
+            ConfigError::MaxDurationConflict => ("", ERR_MAX_DURATION_CONFLICT),
+            ConfigError::PipelineWithoutConnections => ("", ERR_PIPELINE_WITHOUT_CONNECTIONS),
+            ConfigError::GraphqlQueryTwice => ("", ERR_GRAPHQL_QUERY_TWICE),
+            ConfigError::AuthTwice => ("", ERR_AUTH_TWICE),
+            ConfigError::BodyTwice => ("", ERR_BODY_TWICE),
+            ConfigError::BodyWithGraphql => ("", ERR_BODY_WITH_GRAPHQL),
+            ConfigError::FormWithBody => ("", ERR_FORM_WITH_BODY),
+            ConfigError::UrlNotProvided => ("", ERR_URL_NOT_PROVIDED),
+            ConfigError::InvalidRequestsAndConcurrency => ("", ERR_INVALID_REQUESTS_AND_CONCURRENCY),
+        };
+        if value.is_empty() {
+            write!(f, "{}", message)
+        } else {
+            write!(f, "\"{}\"\n{}", value, message)
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
 // Type of test to run
 #[derive(Debug, PartialEq, Clone)]
 pub enum TestType {
@@ -25,20 +365,273 @@ pub enum TestType {
     Both,
 }
 
+// Requests sent before the measured test
+#[derive(Debug, PartialEq, Clone)]
+pub enum Warmup {
+    Requests(usize),
+    Duration(Duration),
+}
+
+// Format of the final report
+#[derive(Debug, PartialEq, Clone)]
+pub enum OutputFormat {
+    Text,
+    Breakdown, // stacked bar of where the mean request time is spent
+    Json, // machine readable summary
+    Prometheus, // final metrics in the Prometheus text exposition format
+    OpenMetrics, // the Prometheus metrics as OpenMetrics, with request id exemplars on the latency buckets
+    Markdown, // GitHub-flavored tables for pasting into PRs and wikis
+}
+
+// One step of a multi-stage load profile (--stage 60s:c=10)
+#[derive(Debug, Clone)]
+pub struct Stage {
+    pub spec: String, // as given, labels the stage in the report
+    pub duration: Duration,
+    pub concurrency: usize,
+}
+
+// Form body built from --form / --form-file
+#[derive(Debug, Clone)]
+pub struct FormBody {
+    pub content_type: String, // urlencoded, or multipart with its boundary
+    pub bytes: Vec<u8>,
+}
+
+// Linear change of the concurrency at the start of a duration test (--ramp 0:1000:30s), held at the end value after
+#[derive(Debug, Clone)]
+pub struct Ramp {
+    pub start: usize,
+    pub end: usize,
+    pub duration: Duration,
+}
+
+// One request of a --script mixed workload (a `[WEIGHT] METHOD URL [BODY-FILE]` line)
+#[derive(Debug, Clone)]
+pub struct RequestSpec {
+    pub method: Method,
+    pub url: String,
+    pub body: Option<Vec<u8>>, // contents of the body file
+    pub weight: usize, // relative share of the requests (1 when not given)
+}
+
+// HTTP version the requests are limited to (negotiated when not given)
+#[derive(Debug, PartialEq, Clone)]
+pub enum HttpVersion {
+    Http11,
+    Http2, // with prior knowledge, no fall back to 1.1 unless ALPN picks it
+}
+
+// Where a followed redirect may lead (with --follow-redirects)
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RedirectScope {
+    SameHost, // same scheme, host and port
+    SameDomain, // any host sharing the last two labels (www.example.com -> api.example.com)
+    Any,
+}
+
+impl RedirectScope {
+    /// Whether a redirect from one url to another may be followed
+    pub fn allows(&self, from: &Url, to: &Url) -> bool {
+        match self {
+            RedirectScope::SameHost => from.origin() == to.origin(),
+            RedirectScope::SameDomain => match (from.domain(), to.domain()) {
+                (Some(from), Some(to)) => Self::base_domain(from).eq_ignore_ascii_case(Self::base_domain(to)),
+                _ => from.host() == to.host(), // ip addresses have no domain
+            },
+            RedirectScope::Any => true,
+        }
+    }
+
+    // last two labels of the host (no public suffix list, so co.uk style domains are too broad)
+    fn base_domain(host: &str) -> &str {
+        let host = host.trim_end_matches('.');
+        match host.rmatch_indices('.').nth(1) {
+            Some((index, _)) => &host[index + 1..],
+            None => host,
+        }
+    }
+}
+
+// Unit the latencies are printed in
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum LatencyUnit {
+    Auto, // ms, the summarized stats switch to seconds above 1000ms
+    Micros,
+    Millis,
+    Secs,
+}
+
+impl LatencyUnit {
+    /// Suffix of the unit (ms in auto mode)
+    pub fn label(&self) -> &'static str {
+        match self {
+            LatencyUnit::Micros => "us",
+            LatencyUnit::Secs => "s",
+            LatencyUnit::Auto | LatencyUnit::Millis => "ms",
+        }
+    }
+
+    /// Format a latency given in ms in the unit (without the suffix)
+    pub fn format(&self, ms: f64) -> String {
+        match self {
+            LatencyUnit::Micros => format!("{:.0}", ms * 1000.0),
+            LatencyUnit::Secs => format!("{:.4}", ms / 1000.0),
+            LatencyUnit::Auto | LatencyUnit::Millis => format!("{:.2}", ms),
+        }
+    }
+}
+
+// Metric a --fail-if condition is checked against
+#[derive(Debug, PartialEq, Clone)]
+pub enum FailMetric {
+    Percentile(f64), // latency (ms) at the percentile
+    AvgLatency, // ms
+    MaxLatency, // ms
+    ErrorRate, // percentage of the finished requests that failed
+    RequestsPerSec,
+    Errors, // failed requests
+    Non2xx, // non 2.x.x responses
+}
+
+// Comparison of a --fail-if condition
+#[derive(Debug, PartialEq, Clone)]
+pub enum Comparison {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+// Condition that fails the run when it holds for the final report (e.g "p99>200ms")
+#[derive(Debug, PartialEq, Clone)]
+pub struct FailCondition {
+    pub expression: String, // as given, for the failure message
+    pub metric: FailMetric,
+    pub comparison: Comparison,
+    pub threshold: f64, // in the metric's unit (ms for latencies)
+}
+
+impl FailCondition {
+    /// Whether the condition holds for the given value of its metric
+    pub fn holds(&self, value: f64) -> bool {
+        match self.comparison {
+            Comparison::Greater => value > self.threshold,
+            Comparison::GreaterOrEqual => value >= self.threshold,
+            Comparison::Less => value < self.threshold,
+            Comparison::LessOrEqual => value <= self.threshold,
+        }
+    }
+}
+
+impl OutputFormat {
+    /// Whether stdout is reserved for the report (progress goes to stderr)
+    pub fn is_machine(&self) -> bool {
+        matches!(self, OutputFormat::Json | OutputFormat::Prometheus | OutputFormat::OpenMetrics | OutputFormat::Markdown)
+    }
+}
+
 // Parse arguments for CLI
 #[derive(Debug, Clone)]
 pub struct Config {
     pub requests: usize,
     pub duration: Duration,
     pub test_type: TestType,
+    pub max_duration: Option<Duration>, // wall clock cap of a request count test, stops it when exceeded
 
     pub concurrency: usize, // number of concurrent requests
+    pub connections: Option<usize>, // max open connections in the client pool
+    pub pipeline: Option<usize>, // requests in flight per connection (concurrency = connections x pipeline)
+    pub rate: Option<usize>, // cap on requests sent per second (across all concurrent requests)
+    pub correct_co: bool, // also record latencies from the scheduled (not the actual) send time of each request
+    pub warmup: Option<Warmup>, // requests sent before measuring, their results are discarded
+    pub think_time: Option<(Duration, Duration)>, // random pause (min, max) before each request, not part of the latency
+    pub seed: Option<u64>, // seed of the rng behind all randomized behavior (random when not given)
+    pub name: Option<String>, // label of the run (e.g baseline-v1.2) carried into every report
+    pub request_id_header: Option<String>, // header carrying a unique id per request (X-Request-Id with -o openmetrics)
     pub threads: usize,
+    pub max_blocking_threads: Option<usize>, // cap on tokio's blocking pool (spawned on top of the worker threads)
     pub timeout: Duration, // total time for request/response cycle including DNS resolution
     pub connection_timeout: Duration, // timeout for establishing connection to the host (not the complete request/response cycle)
+    pub insecure: bool, // accept invalid TLS certificates
+    pub follow_redirects: bool, // follow 3xx responses instead of counting them as non 2.x.x
+    pub max_redirects: Option<usize>, // cap on the redirects followed per request (implies follow_redirects)
+    pub restrict_redirects: RedirectScope, // redirects leading elsewhere are not followed (same host by default)
+    pub no_keepalive: bool, // open a new connection for every request instead of reusing the pool
+    pub no_decompress: bool, // read response bodies as sent (isahc decompresses them otherwise)
+    pub http_version: Option<HttpVersion>, // HTTP version to use instead of negotiating it
+    pub resolve: Vec<(String, u16, IpAddr)>, // host and port pinned to an address (like curl --resolve)
+    pub adaptive_timeout: Option<f64>, // per request deadline as a multiple of the running p99
+    pub retries: usize, // attempts after the first one on connection failures, resets and timeouts
+    pub retry_backoff: Duration, // pause before the first retry, doubled for each further one
+    pub retry_on_5xx: bool, // also retry 5.x.x responses
+    pub max_errors: Option<usize>, // abort once more requests than these failed
+    pub max_error_rate: Option<f64>, // abort once a higher percentage of the requests failed
     pub summarize: bool, // summarize the output
+    pub quiet: bool, // no banner or progress output (the report is still written)
+    pub live: bool, // show the req/s, p50 and p99 of the last second next to the progress
+    pub server_timing: bool, // collect the durations of the Server-Timing response headers
+    pub tokio_console: bool, // install the tokio-console subscriber (tokio-console feature only)
+    pub method: Method, // HTTP method of the requests
+    pub headers: Vec<(String, String)>, // extra request headers
+    pub authorization: Option<String>, // Authorization header value from --basic-auth or --bearer (added to the headers)
+    pub expect_continue: bool, // send Expect: 100-continue with the request bodies and time the wait for it
+    pub cookies: bool, // keep a cookie jar, cookies set by the responses are sent with the later requests
+    pub seed_cookies: Vec<(String, String)>, // --cookie name=value pairs in the jar before the first request
+    pub body: Option<Vec<u8>>, // request body (from --body or --body-file)
+    pub graphql_query: Option<String>, // GraphQL query to POST instead of a plain GET
+    pub graphql_variables: Option<String>, // JSON object sent as the query variables
+    pub graphql_body: Option<Vec<u8>>, // {"query": ..., "variables": ...} envelope built from the above
+    pub form_fields: Vec<(String, String)>, // --form key=value pairs
+    pub form_files: Vec<(String, String)>, // --form-file (field, path) pairs, makes the form multipart
+    pub form_body: Option<FormBody>, // urlencoded or multipart body built from the above
+    pub expect_status: Vec<u16>, // responses with any other status fail (any status passes when empty)
+    pub expect_substring: Option<String>, // responses whose body lacks this fail
+    pub output: OutputFormat,
+    pub latency_unit: LatencyUnit, // unit of the printed latencies (json is always in ms)
+    pub human_stderr: bool, // also write the human readable report to stderr (next to a machine readable one)
+    pub out_file: Option<String>, // write the report into this file instead of stdout
+    pub cert_expiry_days: Option<usize>, // warn if the server certificate expires within these many days
+    pub yes: bool, // skip the confirmation prompt for high-impact runs
+    pub dry_run: bool, // print the plan and exit without sending any request
+    pub verify: Option<String>, // json report whose fingerprint is checked instead of running a test
+    pub confirm_requests: usize, // ask for confirmation above these many requests
+    pub confirm_concurrency: usize, // ask for confirmation above this concurrency
+    pub cache_comparison: bool, // run twice and compare the cold and warm passes
+    pub stages: Vec<Stage>, // load profile run in order (duration and concurrency are the sum / max of these)
+    pub ramp: Option<Ramp>, // concurrency ramp of a duration test (concurrency is the larger end of it)
+    pub strict: bool, // treat warnings as errors
+    pub fail_if: Vec<FailCondition>, // conditions failing the run (exit code 2)
+    pub record: Option<String>, // file to record every sent request into
+    pub timeline: Option<String>, // csv file for the per-second requests, latency and errors
+    pub raw_csv: Option<String>, // csv file with a row per completed request (streamed while running)
+    pub min_samples: usize, // samples needed before percentiles are considered reliable
+    pub percentiles: Vec<f64>, // percentiles shown in the latency distribution
+    pub buckets: usize, // latency histogram buckets
+    pub hist_max: Option<f64>, // upper bound (ms) of the latency histogram, slower requests go in the last bucket
+    pub sla: Option<f64>, // latency threshold (ms) marked on the histogram, the run meets it when p99 is within
 
     pub url: String,
+    pub urls: Vec<String>, // requests are spread across these round-robin (the positional url first)
+    pub urls_sample: Option<usize>, // urls picked at random (seeded) from the --urls-file ones, the others are left out
+    pub urls_in_files: usize, // urls listed in the --urls-file(s), before sampling
+    pub script: Vec<RequestSpec>, // requests sent instead of the urls, round-robin or by weight (with --script)
+}
+
+// Options read from a --config file (TOML), flags given on the command line take precedence
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileConfig {
+    url: Option<String>,
+    requests: Option<usize>,
+    duration: Option<String>, // same format as -d (e.g "30s")
+    concurrency: Option<usize>,
+    threads: Option<usize>,
+    timeout: Option<String>,
+    connection_timeout: Option<String>,
+    method: Option<String>,
+    headers: Option<Vec<String>>, // "Name: Value", sent before the -H ones
+    body: Option<String>,
 }
 
 // Default values, uses total requests test by default
@@ -48,76 +641,469 @@ impl Default for Config {
             requests: 10,
             duration: Duration::from_secs(0),
             test_type: TestType::RequestCount,
+            max_duration: None,
             concurrency: 1,
+            connections: None,
+            pipeline: None,
+            rate: None,
+            correct_co: false,
+            warmup: None,
+            think_time: None,
+            seed: None,
+            name: None,
+            request_id_header: None,
             threads: 1,
+            max_blocking_threads: None,
             timeout: Duration::from_secs(25),
             connection_timeout: Duration::from_secs(20),
+            insecure: false,
+            follow_redirects: false,
+            max_redirects: None,
+            restrict_redirects: RedirectScope::SameHost,
+            no_keepalive: false,
+            no_decompress: false,
+            http_version: None,
+            resolve: Vec::new(),
+            adaptive_timeout: None,
+            retries: 0,
+            retry_backoff: Duration::from_millis(100),
+            retry_on_5xx: false,
+            max_errors: None,
+            max_error_rate: None,
             summarize: false,
+            quiet: false,
+            live: false,
+            server_timing: false,
+            tokio_console: false,
+            method: Method::GET,
+            headers: Vec::new(),
+            expect_continue: false,
+            authorization: None,
+            cookies: false,
+            seed_cookies: Vec::new(),
+            body: None,
+            graphql_query: None,
+            graphql_variables: None,
+            graphql_body: None,
+            form_fields: Vec::new(),
+            form_files: Vec::new(),
+            form_body: None,
+            expect_status: Vec::new(),
+            expect_substring: None,
+            output: OutputFormat::Text,
+            latency_unit: LatencyUnit::Auto,
+            human_stderr: false,
+            out_file: None,
+            cert_expiry_days: None,
+            yes: false,
+            dry_run: false,
+            verify: None,
+            confirm_requests: 100_000,
+            confirm_concurrency: 1000,
+            cache_comparison: false,
+            stages: Vec::new(),
+            ramp: None,
+            strict: false,
+            fail_if: Vec::new(),
+            record: None,
+            timeline: None,
+            raw_csv: None,
+            min_samples: 100,
+            percentiles: vec![50.0, 75.0, 90.0, 99.0],
+            buckets: 10,
+            hist_max: None,
+            sla: None,
             url: "".to_string(),
+            urls: Vec::new(),
+            urls_sample: None,
+            urls_in_files: 0,
+            script: Vec::new(),
+        }
+    }
+}
+
+/// Builds a Config in code instead of from the command line (for embedding the runner),
+/// every option not set here keeps its default and the progress output is off
+#[derive(Debug, Clone)]
+pub struct ConfigBuilder {
+    config: Config,
+    requests_set: bool, // for setting TestType as Both
+}
+
+impl ConfigBuilder {
+    pub fn requests(mut self, requests: usize) -> Self {
+        self.config.requests = requests;
+        self.requests_set = true;
+        if self.config.test_type == TestType::Duration {
+            self.config.test_type = TestType::Both;
+        }
+        self
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.config.duration = duration;
+        self.config.test_type = if self.requests_set {
+            TestType::Both
+        } else {
+            TestType::Duration
+        };
+        self
+    }
+
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.config.concurrency = concurrency;
+        self
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.config.threads = threads;
+        self
+    }
+
+    pub fn rate(mut self, rate: usize) -> Self {
+        self.config.rate = Some(rate);
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout = timeout;
+        self
+    }
+
+    pub fn connection_timeout(mut self, connection_timeout: Duration) -> Self {
+        self.config.connection_timeout = connection_timeout;
+        self
+    }
+
+    pub fn method(mut self, method: Method) -> Self {
+        self.config.method = method;
+        self
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.config.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn body(mut self, body: Vec<u8>) -> Self {
+        self.config.body = Some(body);
+        self
+    }
+
+    /// Accept invalid TLS certificates like -k does
+    pub fn insecure(mut self, insecure: bool) -> Self {
+        self.config.insecure = insecure;
+        self
+    }
+
+    /// Print the banner and progress like the command line does
+    pub fn progress(mut self, progress: bool) -> Self {
+        self.config.quiet = !progress;
+        self
+    }
+
+    /// Validate the options the same way the flags are
+    pub fn build(self) -> Result<Config, ConfigError> {
+        let config = self.config;
+        Config::validate_url(&config.url)?;
+        if config.requests == 0 {
+            return Err(ConfigError::InvalidRequests(config.requests.to_string()));
+        }
+        if config.concurrency == 0 {
+            return Err(ConfigError::InvalidConcurrency(config.concurrency.to_string()));
         }
+        if config.threads == 0 {
+            return Err(ConfigError::InvalidThreads(config.threads.to_string()));
+        }
+        if config.rate == Some(0) {
+            return Err(ConfigError::InvalidRate("0".to_string()));
+        }
+        if config.test_type != TestType::RequestCount && config.duration.is_zero() {
+            return Err(ConfigError::InvalidDuration(String::new()));
+        }
+        if config.timeout.is_zero() {
+            return Err(ConfigError::InvalidTimeout(String::new()));
+        }
+        if config.connection_timeout.is_zero() {
+            return Err(ConfigError::InvalidConnectionTimeout(String::new()));
+        }
+        for (name, value) in &config.headers {
+            if HeaderName::from_bytes(name.as_bytes()).is_err() || HeaderValue::from_str(value).is_err() {
+                return Err(ConfigError::InvalidHeader(format!("{}: {}", name, value)));
+            }
+        }
+        if config.test_type == TestType::RequestCount && config.concurrency > config.requests {
+            return Err(ConfigError::InvalidRequestsAndConcurrency);
+        }
+        Ok(config)
     }
 }
 
 impl Config {
     /*-------------------- Public Functions -------------------*/
-    pub fn parse() -> Config {
+    /// Start building a Config for `url` in code
+    pub fn builder(url: &str) -> ConfigBuilder {
+        ConfigBuilder {
+            config: Config {
+                url: url.to_string(),
+                urls: vec![url.to_string()],
+                quiet: true,
+                yes: true,
+                ..Default::default()
+            },
+            requests_set: false,
+        }
+    }
+
+    /// Parse the process arguments
+    pub fn parse() -> Result<Config, ConfigError> {
+        Self::parse_args(&env::args().collect::<Vec<String>>())
+    }
+
+    /// Parse command line style arguments (the first one is the program name)
+    pub fn parse_args(args: &[String]) -> Result<Config, ConfigError> {
         let mut parsed_config = Self::default();
-        let args: Vec<String> = env::args().collect();
 
         if args.len() == 1 {
             // no arguments given
-            Self::print_help();
-            std::process::exit(0);
+            return Err(ConfigError::Help);
         }
 
         let mut args_iter = args.iter().skip(1); // skip the first argument
         let mut url_provided = false; // so the url is not taken more than once
         let mut req_count_test_provided: bool = false; // for setting TestType as Both
+        let mut concurrency_provided: bool = false; // for reconciling with connections x pipeline
+
+        // the file is applied first so the flags override it, the url and body only fill in missing ones
+        let mut file_url: Option<String> = None;
+        let mut file_body: Option<Vec<u8>> = None;
+        if let Some(path) = Self::find_config_file(args)? {
+            let mut file_config = Self::read_config_file(&path)?;
+            file_url = file_config.url.take();
+            file_body = file_config.body.take().map(String::into_bytes);
+            Self::apply_config_file(
+                &mut parsed_config,
+                file_config,
+                &mut req_count_test_provided,
+                &mut concurrency_provided
+            )?;
+        }
 
         while let Some(arg) = args_iter.next() {
-            if Self::handle_help(arg) || Self::handle_version(arg) {
-                // check for -h / --help  and -v / --version flags
-                std::process::exit(0);
+            // check for -h / --help  and -v / --version flags
+            if arg == "-h" || arg == "--help" {
+                return Err(ConfigError::Help);
+            }
+            if arg == "-v" || arg == "--version" {
+                return Err(ConfigError::Version);
             }
 
             if
-                Self::handle_duration_test(
-                    &mut parsed_config,
-                    arg,
-                    &mut args_iter,
-                    &mut req_count_test_provided
-                ) ||
-                Self::handle_request_count_test(
-                    &mut parsed_config,
-                    arg,
-                    &mut args_iter,
-                    &mut req_count_test_provided
-                ) ||
-                Self::handle_concurrency(&mut parsed_config, arg, &mut args_iter) ||
-                Self::handle_threads(&mut parsed_config, arg, &mut args_iter) ||
-                Self::handle_timeout(&mut parsed_config, arg, &mut args_iter) ||
-                Self::handle_connection_timeout(&mut parsed_config, arg, &mut args_iter) ||
-                Self::handle_summarize(&mut parsed_config, arg) ||
-                Self::handle_url(&mut parsed_config, arg, &mut url_provided)
+                !(Self::handle_config(arg, &mut args_iter)? ||
+                    Self::handle_duration_test(
+                        &mut parsed_config,
+                        arg,
+                        &mut args_iter,
+                        &mut req_count_test_provided
+                    )? ||
+                    Self::handle_request_count_test(
+                        &mut parsed_config,
+                        arg,
+                        &mut args_iter,
+                        &mut req_count_test_provided
+                    )? ||
+                    Self::handle_max_duration(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_concurrency(
+                        &mut parsed_config,
+                        arg,
+                        &mut args_iter,
+                        &mut concurrency_provided
+                    )? ||
+                    Self::handle_connections(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_pipeline(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_rate(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_warmup(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_think_time(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_seed(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_name(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_request_id_header(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_threads(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_max_blocking_threads(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_timeout(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_connection_timeout(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_adaptive_timeout(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_error_limits(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_retries(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_min_samples(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_percentiles(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_buckets(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_hist_max(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_sla(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_cert_expiry(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_record(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_out_file(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_timeline(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_raw_csv(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_method(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_header(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_auth(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_cookies(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_body(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_urls_file(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_urls_sample(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_script(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_graphql(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_form(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_expect(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_output(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_latency_unit(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_summarize(&mut parsed_config, arg) ||
+                    Self::handle_insecure(&mut parsed_config, arg) ||
+                    Self::handle_follow_redirects(&mut parsed_config, arg) ||
+                    Self::handle_max_redirects(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_restrict_redirects(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_no_keepalive(&mut parsed_config, arg) ||
+                    Self::handle_no_decompress(&mut parsed_config, arg) ||
+                    Self::handle_http_version(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_resolve(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_quiet(&mut parsed_config, arg) ||
+                    Self::handle_live(&mut parsed_config, arg) ||
+                    Self::handle_server_timing(&mut parsed_config, arg) ||
+                    Self::handle_expect_continue(&mut parsed_config, arg) ||
+                    Self::handle_tokio_console(&mut parsed_config, arg)? ||
+                    Self::handle_correct_co(&mut parsed_config, arg) ||
+                    Self::handle_human_stderr(&mut parsed_config, arg) ||
+                    Self::handle_strict(&mut parsed_config, arg) ||
+                    Self::handle_fail_if(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_cache_comparison(&mut parsed_config, arg) ||
+                    Self::handle_stage(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_ramp(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_yes(&mut parsed_config, arg) ||
+                    Self::handle_dry_run(&mut parsed_config, arg) ||
+                    Self::handle_verify(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_confirm_thresholds(&mut parsed_config, arg, &mut args_iter)? ||
+                    Self::handle_url(&mut parsed_config, arg, &mut url_provided)?)
             {
-                continue;
-            } else {
-                Self::print_help();
-                std::process::exit(1);
+                return Err(ConfigError::UnknownArgument(arg.clone()));
             }
         }
+        if parsed_config.verify.is_some() {
+            return Ok(parsed_config); // nothing is sent, the test options don't matter
+        }
 
         if !url_provided {
-            eprintln!("{}", ERR_URL_NOT_PROVIDED);
-            std::process::exit(1);
+            if let Some(url) = file_url {
+                Self::parse_url(&mut parsed_config, &url)?;
+                url_provided = true;
+            }
+        }
+        if parsed_config.body.is_none() {
+            parsed_config.body = file_body;
+        }
+        if let Some(authorization) = parsed_config.authorization.clone() {
+            parsed_config.headers.push(("Authorization".to_string(), authorization));
+        }
+        Self::substitute_env_vars(&mut parsed_config)?;
+
+        parsed_config.urls_in_files = parsed_config.urls.len();
+        if let Some(sample) = parsed_config.urls_sample {
+            if parsed_config.urls.is_empty() {
+                return Err(ConfigError::UrlsSampleWithoutFile);
+            }
+            // the seed is fixed here so a run replayed with --seed samples the same urls
+            let seed = *parsed_config.seed.get_or_insert_with(rand::random);
+            let mut picked = rand::seq::index
+                ::sample(&mut StdRng::seed_from_u64(seed), parsed_config.urls.len(), sample.min(parsed_config.urls.len()))
+                .into_vec();
+            picked.sort_unstable(); // in the order of the file
+            parsed_config.urls = picked.into_iter().map(|i| parsed_config.urls[i].clone()).collect();
+        }
+
+        if url_provided {
+            parsed_config.urls.insert(0, parsed_config.url.clone());
+        } else if let Some(first) = parsed_config.urls.first() {
+            parsed_config.url = first.clone(); // only --urls-file was given
+        } else if let Some(first) = parsed_config.script.first() {
+            parsed_config.url = first.url.clone(); // only --script was given
+            parsed_config.urls.push(first.url.clone());
+        } else {
+            return Err(ConfigError::UrlNotProvided);
+        }
+
+        if !parsed_config.stages.is_empty() {
+            if
+                req_count_test_provided ||
+                parsed_config.test_type != TestType::RequestCount ||
+                concurrency_provided ||
+                parsed_config.pipeline.is_some() ||
+                parsed_config.cache_comparison
+            {
+                return Err(ConfigError::StageConflict);
+            }
+            // the whole profile is one duration test, sized for its busiest stage
+            parsed_config.test_type = TestType::Duration;
+            parsed_config.duration = parsed_config.stages.iter().map(|stage| stage.duration).sum();
+            parsed_config.concurrency = parsed_config.stages
+                .iter()
+                .map(|stage| stage.concurrency)
+                .max()
+                .unwrap_or(1);
+        }
+        if parsed_config.max_duration.is_some() && parsed_config.test_type != TestType::RequestCount {
+            return Err(ConfigError::MaxDurationConflict); // the duration already bounds the test
+        }
+        if let Some(ramp) = &parsed_config.ramp {
+            if
+                parsed_config.test_type == TestType::RequestCount ||
+                concurrency_provided ||
+                parsed_config.pipeline.is_some() ||
+                !parsed_config.stages.is_empty()
+            {
+                return Err(ConfigError::RampConflict);
+            }
+            parsed_config.concurrency = ramp.start.max(ramp.end); // sizes the pool for the busiest point
+        }
+        Self::reconcile_pipeline(&mut parsed_config, concurrency_provided)?;
+        if parsed_config.correct_co && parsed_config.rate.is_none() {
+            return Err(ConfigError::CorrectCoWithoutRate);
+        }
+        if parsed_config.retry_on_5xx && parsed_config.retries == 0 {
+            return Err(ConfigError::RetryOn5xxWithoutRetries);
+        }
+        Self::build_graphql_body(&mut parsed_config)?;
+        Self::build_form_body(&mut parsed_config)?;
+
+        if parsed_config.body.is_some() && parsed_config.graphql_body.is_some() {
+            return Err(ConfigError::BodyWithGraphql);
+        }
+        if
+            parsed_config.form_body.is_some() &&
+            (parsed_config.body.is_some() || parsed_config.graphql_body.is_some())
+        {
+            return Err(ConfigError::FormWithBody);
+        }
+        if
+            parsed_config.expect_continue &&
+            parsed_config.request_body().is_none() &&
+            parsed_config.script.iter().all(|spec| spec.body.is_none())
+        {
+            return Err(ConfigError::ExpectContinueWithoutBody);
+        }
+
+        if parsed_config.output == OutputFormat::OpenMetrics && parsed_config.request_id_header.is_none() {
+            parsed_config.request_id_header = Some("X-Request-Id".to_string()); // the exemplars point at these ids
+        }
+        if parsed_config.human_stderr && !parsed_config.output.is_machine() {
+            return Err(ConfigError::HumanStderrNeedsMachineOutput);
         }
 
-        if parsed_config.concurrency > parsed_config.requests {
-            eprintln!("{}", ERR_INVALID_REQUESTS_AND_CONCURRENCY);
-            std::process::exit(1);
+        // only a pure request count test is sized by the requests, with a duration
+        // the concurrency is the load level (and the request count may be left at its default)
+        if parsed_config.test_type == TestType::RequestCount && parsed_config.concurrency > parsed_config.requests {
+            return Err(ConfigError::InvalidRequestsAndConcurrency);
         }
 
-        parsed_config
+        Ok(parsed_config)
     }
 
     pub fn print_help() {
@@ -127,21 +1113,160 @@ impl Config {
         println!("{} powered by nayaraasta", name);
         println!();
         println!("Options:");
-        println!("  -n, --requests           <N>  Number of requests (Default: 10)");
+        println!("  -n, --requests           <N>  Number of requests, e.g 500k, 2M (Default: 10)");
         println!("  -d, --duration           <D>  Test duration");
+        println!("      --max-duration       <D>  Stop a request count test that runs longer than D");
         println!("  -c, --concurrency        <N>  Concurrent requests (Default: 1)");
+        println!("      --connections        <N>  Max open connections (Default: no limit)");
+        println!("      --pipeline           <N>  Requests per connection (concurrency = connections x N)");
+        println!("      --rate               <N>  Max requests per second");
+        println!("      --correct-co              Also measure latency from the scheduled start (needs --rate)");
+        println!("      --warmup           <N|D>  Requests (or duration) sent before measuring");
+        println!("      --think-time       <D-D>  Random pause before each request (not timed)");
+        println!("      --seed               <N>  Seed for the random choices, to replay a run");
         println!("  -t, --threads            <N>  Number of threads (Default: 1)");
+        println!("      --max-blocking-threads <N> Max blocking threads (Default: 512)");
         println!("  -T, --timeout            <D>  Request timeout (Default: 25s)");
         println!("  -C, --connection-timeout <D>  Connection timeout (Default: 20s)");
+        println!("      --adaptive-timeout   <M>  Time out requests after M x the running p99");
+        println!("      --expect-status      <L>  Fail responses with another status, e.g 200,204");
+        println!("      --expect-substring   <S>  Fail responses whose body does not contain S");
+        println!("      --retries            <N>  Retry failed connections, resets and timeouts N times");
+        println!("      --retry-backoff      <D>  Pause before a retry, doubled each time (Default: 100ms)");
+        println!("      --retry-on-5xx            Also retry 5.x.x responses (needs --retries)");
+        println!("      --max-errors         <N>  Abort once more than N requests failed");
+        println!("      --max-error-rate   <PCT>  Abort once more than PCT% of the requests failed");
+        println!("  -X, --method             <M>  HTTP method (Default: GET)");
+        println!("  -H, --header             <H>  Request header \"Name: Value\" (repeatable)");
+        println!("      --basic-auth <USER:PASS>  Send basic auth credentials (sets Authorization)");
+        println!("      --bearer         <TOKEN>  Send a bearer token (sets Authorization)");
+        println!("      --cookies                 Keep the cookies set by responses and send them back");
+        println!("      --cookie    <NAME=VALUE>  Start the cookie jar with a cookie (repeatable, implies --cookies)");
+        println!("      --body               <S>  Request body");
+        println!("      --body-file       <PATH>  Read the request body from a file");
+        println!("      --form       <KEY=VALUE>  Send an urlencoded form field (repeatable)");
+        println!("      --form-file <FIELD=@PATH> Upload a file as multipart form data (repeatable)");
+        println!("      --expect-continue         Send Expect: 100-continue and time the wait for it and the upload");
+        println!("      --urls-file       <PATH>  Spread requests round-robin across the URLs in a file");
+        println!("      --urls-sample        <N>  Only use N URLs of the file, picked at random (seeded)");
+        println!("      --script          <PATH>  Mixed workload, one [WEIGHT] METHOD URL [BODY-FILE] per line");
+        println!("      --graphql            <Q>  POST a GraphQL query (counts responses with errors)");
+        println!("      --graphql-file    <PATH>  Read the GraphQL query from a file");
+        println!("      --graphql-variables <J>   JSON object with the query variables");
+        println!("  -k, --insecure                Accept invalid TLS certificates");
+        println!("  -L, --follow-redirects        Follow redirects (3xx are non 2.x.x otherwise)");
+        println!("      --max-redirects      <N>  Max redirects followed per request (implies -L)");
+        println!("      --restrict-redirects <S>  Only follow redirects to: same-host, same-domain, any (Default: same-host)");
+        println!("      --no-keepalive            Open a new connection for every request");
+        println!("      --http-version       <V>  Only use HTTP 1.1 or 2 (Default: negotiated)");
+        println!("      --resolve <HOST:PORT:IP>  Connect to IP for HOST:PORT, the Host header is kept (repeatable)");
+        println!("      --no-decompress           Keep compressed bodies as is (no Accept-Encoding is sent)");
         println!("  -s                            Summarize output");
+        println!("  -q, --quiet                   No banner or progress output, only the report");
+        println!("      --live                    Show req/s, p50 and p99 of the last second while running");
+        println!("      --tokio-console           Serve the runtime to tokio-console (tokio-console feature)");
+        println!("      --server-timing           Report the backend durations of the Server-Timing header");
+        println!("      --name               <S>  Label of the run, included in every report");
+        println!("      --request-id-header  <H>  Send a unique id per request in header H (Default with -o openmetrics: X-Request-Id)");
+        println!("  -o, --output             <F>  Report format: text, json, prometheus, openmetrics, markdown, breakdown (Default: text)");
+        println!("      --latency-unit       <U>  Latency unit: auto, us, ms, s (Default: auto)");
+        println!("      --min-samples        <N>  Samples needed for reliable percentiles (Default: 100)");
+        println!("      --percentiles        <L>  Percentiles to show (Default: 50,75,90,99)");
+        println!("      --buckets            <N>  Latency histogram buckets (Default: 10)");
+        println!("      --hist-max          <MS>  Clamp the latency histogram (outliers in the last bucket)");
+        println!("      --sla               <MS>  Mark a latency SLA on the histogram, passes when p99 is within");
+        println!("      --out-file        <PATH>  Write the report into a file (progress goes to stderr)");
+        println!("      --human-stderr            Also write the human readable report to stderr");
+        println!("      --check-cert-expiry  <N>  Warn if the certificate expires within N days");
+        println!("      --record          <PATH>  Record every sent request into a file (one JSON line each)");
+        println!("      --timeline        <PATH>  Write per-second requests, latency and errors as CSV");
+        println!("      --raw-csv         <PATH>  Write index, latency, status and bytes of every request as CSV");
+        println!("      --strict                  Exit with an error if any warning was raised");
+        println!("      --fail-if            <C>  Exit with code 2 if C holds, e.g \"p99>200ms\" (repeatable)");
+        println!("      --cache-comparison        Run twice and compare cold vs warm latencies");
+        println!("      --stage          <D:c=N>  Run D at concurrency N, stages run in order (repeatable)");
+        println!("      --ramp           <S:E:D>  Ramp concurrency from S to E over D, then hold E (needs -d)");
+        println!("  -y, --yes                     Do not ask for confirmation before large runs");
+        println!("      --dry-run                 Print the resolved plan and exit without sending requests");
+        println!("      --verify          <PATH>  Check the fingerprint of a json report and exit");
+        println!("      --confirm-requests   <N>  Ask before sending more than N requests (Default: 100000)");
+        println!("      --confirm-concurrency <N> Ask before exceeding N concurrency (Default: 1000)");
+        println!("      --config          <PATH>  Read options from a TOML file (flags override it)");
         println!("  -h, --help                    Print help (this)");
         println!("  -v, --version                 Print version");
         println!();
         println!("Arguments:");
-        println!("  <URL>                         URL to test");
+        println!("  <URL>                         URL to test (optional with --urls-file or --script)");
         println!();
-        println!("Durations can be specified like: 10s, 1m, 1h");
+        println!("Values follow the flag as -t 4, -t4, --threads 4 or --threads=4");
+        println!("Durations can be specified like: 10s, 500ms, 1.5s, 1m30s, 1h");
+        println!("--form and --form-file bodies are POSTed unless another -X method is given");
+        println!("Headers and bodies can reference environment variables like ${{API_TOKEN}}");
+        println!("Blocking threads are spawned on demand in addition to the -t worker threads");
+        println!("--adaptive-timeout uses the static -T timeout for the first 100 requests");
+        println!("--max-error-rate applies after the first 100 requests, an abort prints the report so far and exits with code 3");
         println!("The test ends when either -n or -d completes. (if both are given)");
+        println!("--warmup takes a request count (1000) or a duration (5s)");
+        println!("--think-time takes a range (100ms-500ms) or a fixed pause (200ms)");
+        println!("The json report carries a fingerprint of its counts, percentiles and rps, --verify recomputes it");
+        println!("-L only follows redirects to the same host by default, a blocked redirect counts as a 3xx response");
+        println!("-o openmetrics links each latency bucket to the id of its latest request (exemplars)");
+        println!("--check-cert-expiry only applies to https URLs and inspects the certificate via OpenSSL");
+        println!("Warnings (failures with --strict): near-expiry certificate, saturated connection pool");
+        println!("--fail-if metrics: p<N>, avg, max (ms or s), error_rate (%), rps, errors, non_2xx");
+    }
+
+    /// Whether the requested load is large enough to ask the user before starting
+    pub fn is_high_impact(&self) -> bool {
+        let counts_requests = self.test_type != TestType::Duration;
+        (counts_requests && self.requests > self.confirm_requests) ||
+            self.concurrency > self.confirm_concurrency
+    }
+
+    /// Urls picked from the --urls-file(s) with --urls-sample (they come after the positional url)
+    pub fn sampled_urls(&self) -> Option<&[String]> {
+        let sampled = self.urls_sample?.min(self.urls_in_files);
+        Some(&self.urls[self.urls.len() - sampled..])
+    }
+
+    /// Sockets the test keeps open at most (never more connections than in-flight requests, nor than the pool allows)
+    pub fn sockets(&self) -> usize {
+        self.connections.map_or(self.concurrency, |connections| connections.min(self.concurrency))
+    }
+
+    /// Open file limit the process needs for the sockets of the test
+    pub fn open_files_needed(&self) -> u64 {
+        (self.sockets() as u64) + FD_HEADROOM
+    }
+
+    /// Method the requests are sent with (GraphQL queries and forms are POSTed unless -X says otherwise)
+    pub fn request_method(&self) -> Method {
+        if self.graphql_body.is_some() || (self.form_body.is_some() && self.method == Method::GET) {
+            Method::POST
+        } else {
+            self.method.clone()
+        }
+    }
+
+    /// Body of the requests with the content type it needs (None when -H decides)
+    pub fn request_body(&self) -> Option<(&[u8], Option<&str>)> {
+        if let Some(body) = &self.graphql_body {
+            Some((body, Some("application/json")))
+        } else if let Some(form) = &self.form_body {
+            Some((&form.bytes, Some(&form.content_type)))
+        } else {
+            self.body.as_ref().map(|body| (body.as_slice(), None))
+        }
+    }
+
+    /// Concurrency the test runs at after elapsed, following the --ramp when given
+    pub fn concurrency_at(&self, elapsed: Duration) -> usize {
+        let Some(ramp) = &self.ramp else {
+            return self.concurrency;
+        };
+        let progress = (elapsed.as_secs_f64() / ramp.duration.as_secs_f64()).min(1.0);
+        let concurrency = (ramp.start as f64) + ((ramp.end as f64) - (ramp.start as f64)) * progress;
+        (concurrency.round() as usize).max(1) // a ramp from 0 still sends one request at a time
     }
 
     /*---------------- Private/Helpers ------------------*/
@@ -150,18 +1275,35 @@ impl Config {
         arg: &str,
         args_iter: &mut Skip<Iter<String>>,
         req_count_test_provided: &mut bool
-    ) -> bool {
-        if arg.starts_with("-d") || arg.starts_with("--duration") {
-            Self::parse_duration(parsed_config, arg, args_iter);
+    ) -> Result<bool, ConfigError> {
+        if Self::short_flag(arg, "-d").is_some() || Self::long_flag(arg, "--duration").is_some() {
+            Self::parse_duration(parsed_config, arg, args_iter)?;
             if *req_count_test_provided {
                 parsed_config.test_type = TestType::Both;
             } else {
                 parsed_config.test_type = TestType::Duration;
             }
-            true
+            Ok(true)
         } else {
-            false
+            Ok(false)
+        }
+    }
+
+    fn handle_max_duration(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        let Some(strip) = Self::long_flag(arg, "--max-duration") else {
+            return Ok(false);
+        };
+        let value = Self::parse_string_value(strip, args_iter, ConfigError::InvalidMaxDuration)?;
+        let max_duration = Self::parse_duration_string(&value, ConfigError::InvalidMaxDuration)?;
+        if max_duration.is_zero() {
+            return Err(ConfigError::InvalidMaxDuration(value));
         }
+        parsed_config.max_duration = Some(max_duration);
+        Ok(true)
     }
 
     fn handle_request_count_test(
@@ -169,339 +1311,2360 @@ impl Config {
         arg: &str,
         args_iter: &mut Skip<Iter<String>>,
         req_count_test_provided: &mut bool
-    ) -> bool {
-        if arg.starts_with("-n") || arg.starts_with("--requests") {
-            Self::parse_requests(parsed_config, arg, args_iter);
+    ) -> Result<bool, ConfigError> {
+        if Self::short_flag(arg, "-n").is_some() || Self::long_flag(arg, "--requests").is_some() {
+            Self::parse_requests(parsed_config, arg, args_iter)?;
             if parsed_config.test_type == TestType::Duration {
                 parsed_config.test_type = TestType::Both;
             }
             *req_count_test_provided = true;
-            true
+            Ok(true)
         } else {
-            false
+            Ok(false)
         }
     }
 
     fn handle_concurrency(
         parsed_config: &mut Config,
         arg: &str,
-        args_iter: &mut Skip<Iter<String>>
-    ) -> bool {
-        if arg.starts_with("-c") || arg.starts_with("--concurrency") {
-            Self::parse_concurrency(parsed_config, arg, args_iter);
-            true
+        args_iter: &mut Skip<Iter<String>>,
+        concurrency_provided: &mut bool
+    ) -> Result<bool, ConfigError> {
+        if Self::short_flag(arg, "-c").is_some() || Self::long_flag(arg, "--concurrency").is_some() {
+            Self::parse_concurrency(parsed_config, arg, args_iter)?;
+            *concurrency_provided = true;
+            Ok(true)
         } else {
-            false
+            Ok(false)
         }
     }
 
-    fn handle_threads(
+    fn handle_connections(
         parsed_config: &mut Config,
         arg: &str,
         args_iter: &mut Skip<Iter<String>>
-    ) -> bool {
-        if arg.starts_with("-t") || arg.starts_with("--threads") {
-            Self::parse_threads(parsed_config, arg, args_iter);
-            true
+    ) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--connections") {
+            let connections = Self::parse_usize_value(strip, args_iter, ConfigError::InvalidConnections)?;
+            if connections == 0 {
+                return Err(ConfigError::InvalidConnections(connections.to_string()));
+            }
+            parsed_config.connections = Some(connections);
+            Ok(true)
         } else {
-            false
+            Ok(false)
         }
     }
 
-    fn handle_timeout(
+    fn handle_rate(
         parsed_config: &mut Config,
         arg: &str,
         args_iter: &mut Skip<Iter<String>>
-    ) -> bool {
-        if arg.starts_with("-T") || arg.starts_with("--timeout") {
-            Self::parse_timeout(parsed_config, arg, args_iter);
-            true
+    ) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--rate") {
+            let rate = Self::parse_usize_value(strip, args_iter, ConfigError::InvalidRate)?;
+            if rate == 0 {
+                return Err(ConfigError::InvalidRate(rate.to_string()));
+            }
+            parsed_config.rate = Some(rate);
+            Ok(true)
         } else {
-            false
+            Ok(false)
         }
     }
 
-    fn handle_connection_timeout(
+    fn handle_warmup(
         parsed_config: &mut Config,
         arg: &str,
         args_iter: &mut Skip<Iter<String>>
-    ) -> bool {
-        if arg.starts_with("-C") || arg.starts_with("--connection-timeout") {
-            Self::parse_connection_timeout(parsed_config, arg, args_iter);
-            true
+    ) -> Result<bool, ConfigError> {
+        let strip = if let Some(strip) = Self::long_flag(arg, "--warmup") {
+            strip
         } else {
-            false
-        }
-    }
+            return Ok(false);
+        };
+        let value = Self::parse_string_value(strip, args_iter, ConfigError::InvalidWarmup)?;
 
-    fn handle_summarize(parsed_config: &mut Config, arg: &str) -> bool {
-        if arg == "-s" {
-            parsed_config.summarize = true;
-            true
+        // a plain number is a request count, anything with a unit a duration
+        let warmup = if let Ok(count) = value.parse::<usize>() {
+            Warmup::Requests(count)
         } else {
-            false
+            Warmup::Duration(Self::parse_duration_string(&value, ConfigError::InvalidWarmup)?)
+        };
+        if warmup == Warmup::Requests(0) || warmup == Warmup::Duration(Duration::ZERO) {
+            return Err(ConfigError::InvalidWarmup(value));
         }
+        parsed_config.warmup = Some(warmup);
+        Ok(true)
     }
 
-    fn handle_help(arg: &str) -> bool {
-        if arg == "-h" || arg == "--help" {
-            Self::print_help();
-            true
+    fn handle_think_time(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        let strip = if let Some(strip) = Self::long_flag(arg, "--think-time") {
+            strip
         } else {
-            false
+            return Ok(false);
+        };
+        let value = Self::parse_string_value(strip, args_iter, ConfigError::InvalidThinkTime)?;
+
+        let (min, max) = match value.split_once('-') {
+            Some((min, max)) =>
+                (
+                    Self::parse_duration_string(min, ConfigError::InvalidThinkTime)?,
+                    Self::parse_duration_string(max, ConfigError::InvalidThinkTime)?,
+                ),
+            None => {
+                let pause = Self::parse_duration_string(&value, ConfigError::InvalidThinkTime)?;
+                (pause, pause)
+            }
+        };
+        if max.is_zero() || min > max {
+            return Err(ConfigError::InvalidThinkTime(value));
         }
+        parsed_config.think_time = Some((min, max));
+        Ok(true)
     }
 
-    fn handle_version(arg: &str) -> bool {
-        if arg == "-v" || arg == "--version" {
-            let name = env!("CARGO_PKG_NAME");
-            let version = env!("CARGO_PKG_VERSION");
-            println!("{} {}", name, version);
-            true
+    fn handle_seed(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--seed") {
+            let seed = Self::parse_string_value(strip, args_iter, ConfigError::InvalidSeed)?;
+            parsed_config.seed = Some(seed.parse().map_err(|_| ConfigError::InvalidSeed(seed))?);
+            Ok(true)
         } else {
-            false
+            Ok(false)
         }
     }
-    fn handle_url(parsed_config: &mut Config, arg: &str, is_url_set: &mut bool) -> bool {
-        if !*is_url_set && !arg.starts_with("-") {
-            Self::parse_url(parsed_config, arg);
-            *is_url_set = true;
-            true
-        } else {
-            false
+
+    fn handle_name(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        let Some(strip) = Self::long_flag(arg, "--name") else {
+            return Ok(false);
+        };
+        let name = Self::parse_string_value(strip, args_iter, ConfigError::InvalidName)?;
+        if name.trim().is_empty() {
+            return Err(ConfigError::InvalidName(name));
         }
+        parsed_config.name = Some(name);
+        Ok(true)
     }
 
-    fn parse_url(parsed_config: &mut Config, url: &str) {
-        // Check if the url is correct
-        if !Url::parse(url).is_ok() {
-            println!("\"{}\"\n{}", url, ERR_INVALID_URL);
-            std::process::exit(1);
+    fn handle_request_id_header(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        let Some(strip) = Self::long_flag(arg, "--request-id-header") else {
+            return Ok(false);
+        };
+        let name = Self::parse_string_value(strip, args_iter, ConfigError::InvalidRequestIdHeader)?;
+        if HeaderName::from_bytes(name.as_bytes()).is_err() {
+            return Err(ConfigError::InvalidRequestIdHeader(name));
         }
-        parsed_config.url = url.to_string();
+        parsed_config.request_id_header = Some(name);
+        Ok(true)
     }
 
-    fn parse_requests(parsed_config: &mut Config, arg: &str, args_iter: &mut Skip<Iter<String>>) {
-        if let Some(strip) = arg.strip_prefix("-n") {
-            parsed_config.requests = strip
-                .parse()
-                .unwrap_or_else(|_|
-                    Self::parse_with_next_usize(args_iter, strip, &ERR_INVALID_REQUESTS)
-                );
-        } else if let Some(strip) = arg.strip_prefix("--requests") {
-            parsed_config.requests = strip
-                .parse()
-                .unwrap_or_else(|_|
-                    Self::parse_with_next_usize(args_iter, strip, &ERR_INVALID_REQUESTS)
-                );
+    fn handle_pipeline(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--pipeline") {
+            let pipeline = Self::parse_usize_value(strip, args_iter, ConfigError::InvalidPipeline)?;
+            if pipeline == 0 {
+                return Err(ConfigError::InvalidPipeline(pipeline.to_string()));
+            }
+            parsed_config.pipeline = Some(pipeline);
+            Ok(true)
         } else {
-            eprintln!("{}", ERR_INVALID_REQUESTS);
-            std::process::exit(1);
+            Ok(false)
         }
+    }
+
+    // derive concurrency from connections x pipeline depth, an explicit -c must agree with it
+    fn reconcile_pipeline(parsed_config: &mut Config, concurrency_provided: bool) -> Result<(), ConfigError> {
+        let Some(pipeline) = parsed_config.pipeline else {
+            return Ok(());
+        };
+        let Some(connections) = parsed_config.connections else {
+            return Err(ConfigError::PipelineWithoutConnections);
+        };
 
-        if parsed_config.requests == 0 {
-            eprintln!("{}", ERR_INVALID_REQUESTS);
-            std::process::exit(1);
+        let effective_concurrency = connections * pipeline;
+        if concurrency_provided && parsed_config.concurrency != effective_concurrency {
+            return Err(ConfigError::ContradictingConcurrency {
+                concurrency: parsed_config.concurrency,
+                connections,
+                pipeline,
+            });
         }
+        parsed_config.concurrency = effective_concurrency;
+        Ok(())
     }
 
-    fn parse_concurrency(
+    fn handle_threads(
         parsed_config: &mut Config,
         arg: &str,
         args_iter: &mut Skip<Iter<String>>
-    ) {
-        if let Some(strip) = arg.strip_prefix("-c") {
-            parsed_config.concurrency = strip
-                .parse()
-                .unwrap_or_else(|_|
-                    Self::parse_with_next_usize(args_iter, strip, &ERR_INVALID_CONCURRENCY)
-                );
-        } else if let Some(strip) = arg.strip_prefix("--concurrency") {
-            parsed_config.concurrency = strip
-                .parse()
-                .unwrap_or_else(|_|
-                    Self::parse_with_next_usize(args_iter, strip, &ERR_INVALID_CONCURRENCY)
-                );
+    ) -> Result<bool, ConfigError> {
+        if Self::short_flag(arg, "-t").is_some() || Self::long_flag(arg, "--threads").is_some() {
+            Self::parse_threads(parsed_config, arg, args_iter)?;
+            Ok(true)
         } else {
-            eprintln!("{}", ERR_INVALID_CONCURRENCY);
-            std::process::exit(1);
+            Ok(false)
         }
+    }
 
-        if parsed_config.concurrency == 0 {
-            eprintln!("{}", ERR_INVALID_CONCURRENCY);
-            std::process::exit(1);
+    fn handle_max_blocking_threads(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--max-blocking-threads") {
+            let max_blocking_threads = Self::parse_usize_value(
+                strip,
+                args_iter,
+                ConfigError::InvalidMaxBlockingThreads
+            )?;
+            if max_blocking_threads == 0 {
+                return Err(ConfigError::InvalidMaxBlockingThreads(max_blocking_threads.to_string()));
+            }
+            parsed_config.max_blocking_threads = Some(max_blocking_threads);
+            Ok(true)
+        } else {
+            Ok(false)
         }
     }
 
-    fn parse_threads(parsed_config: &mut Config, arg: &str, args_iter: &mut Skip<Iter<String>>) {
-        if let Some(strip) = arg.strip_prefix("-t") {
-            parsed_config.threads = strip
-                .parse()
-                .unwrap_or_else(|_|
-                    Self::parse_with_next_usize(args_iter, strip, &ERR_INVALID_THREADS)
-                );
-        } else if let Some(strip) = arg.strip_prefix("--threads") {
-            parsed_config.threads = strip
-                .parse()
-                .unwrap_or_else(|_|
-                    Self::parse_with_next_usize(args_iter, strip, &ERR_INVALID_THREADS)
-                );
+    fn handle_timeout(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if Self::short_flag(arg, "-T").is_some() || Self::long_flag(arg, "--timeout").is_some() {
+            Self::parse_timeout(parsed_config, arg, args_iter)?;
+            Ok(true)
         } else {
-            eprintln!("{}", ERR_INVALID_THREADS);
-            std::process::exit(1);
+            Ok(false)
         }
+    }
 
-        if parsed_config.threads == 0 {
-            eprintln!("{}", ERR_INVALID_THREADS);
-            std::process::exit(1);
+    fn handle_connection_timeout(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if Self::short_flag(arg, "-C").is_some() || Self::long_flag(arg, "--connection-timeout").is_some() {
+            Self::parse_connection_timeout(parsed_config, arg, args_iter)?;
+            Ok(true)
+        } else {
+            Ok(false)
         }
     }
 
-    // for -n 10 (space between flag and value)
-    fn parse_with_next_usize(
-        args_iter: &mut Skip<Iter<String>>,
-        strip: &str,
-        error_msg: &str
-    ) -> usize {
-        if !strip.is_empty() {
-            eprintln!("{}", error_msg); // other (invalid) characters were written after the flag
-            std::process::exit(1);
-        }
-        args_iter
-            .next()
-            .and_then(|next| next.parse().ok())
-            .unwrap_or_else(|| {
-                eprintln!("{}", error_msg);
-                std::process::exit(1);
-            })
+    fn handle_adaptive_timeout(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--adaptive-timeout") {
+            let value = Self::parse_string_value(strip, args_iter, ConfigError::InvalidAdaptiveTimeout)?;
+            let multiplier = value
+                .parse::<f64>()
+                .ok()
+                .filter(|multiplier| *multiplier >= 1.0)
+                .ok_or(ConfigError::InvalidAdaptiveTimeout(value))?;
+            parsed_config.adaptive_timeout = Some(multiplier);
+            Ok(true)
+        } else {
+            Ok(false)
+        }
     }
 
-    /* ----Durations ----*/
-    // Parse the duration flag
-    fn parse_duration(parsed_config: &mut Config, arg: &str, args_iter: &mut Skip<Iter<String>>) {
-        let duration_str: String;
-        if let Some(strip) = arg.strip_prefix("-d") {
-            duration_str = strip
-                .parse()
-                .unwrap_or_else(|_|
-                    Self::parse_with_next_duration(args_iter, &ERR_INVALID_DURATION)
-                );
-        } else if let Some(strip) = arg.strip_prefix("--duration") {
-            duration_str = strip
-                .parse()
-                .unwrap_or_else(|_|
-                    Self::parse_with_next_duration(args_iter, &ERR_INVALID_DURATION)
-                );
+    fn handle_retries(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--retries") {
+            parsed_config.retries = Self::parse_usize_value(strip, args_iter, ConfigError::InvalidRetries)?;
+            Ok(true)
+        } else if let Some(strip) = Self::long_flag(arg, "--retry-backoff") {
+            let value = Self::parse_string_value(strip, args_iter, ConfigError::InvalidRetryBackoff)?;
+            parsed_config.retry_backoff = Self::parse_duration_string(&value, ConfigError::InvalidRetryBackoff)?;
+            Ok(true)
+        } else if arg == "--retry-on-5xx" {
+            parsed_config.retry_on_5xx = true;
+            Ok(true)
         } else {
-            eprintln!("{}", ERR_INVALID_DURATION);
-            std::process::exit(1);
+            Ok(false)
         }
-        parsed_config.duration = Self::parse_duration_string(&duration_str, &ERR_INVALID_DURATION);
-        if parsed_config.duration.as_secs() == 0 {
-            eprintln!("{}", ERR_INVALID_DURATION);
-            std::process::exit(1);
+    }
+
+    fn handle_error_limits(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--max-errors") {
+            parsed_config.max_errors = Some(
+                Self::parse_usize_value(strip, args_iter, ConfigError::InvalidMaxErrors)?
+            );
+            Ok(true)
+        } else if let Some(strip) = Self::long_flag(arg, "--max-error-rate") {
+            let value = Self::parse_string_value(strip, args_iter, ConfigError::InvalidMaxErrorRate)?;
+            let max_error_rate = value
+                .trim_end_matches('%')
+                .parse::<f64>()
+                .ok()
+                .filter(|rate| (0.0..100.0).contains(rate))
+                .ok_or(ConfigError::InvalidMaxErrorRate(value))?;
+            parsed_config.max_error_rate = Some(max_error_rate);
+            Ok(true)
+        } else {
+            Ok(false)
         }
     }
 
-    fn parse_timeout(parsed_config: &mut Config, arg: &str, args_iter: &mut Skip<Iter<String>>) {
-        let duration_str: String;
-        if let Some(strip) = arg.strip_prefix("-T") {
-            duration_str = strip
-                .parse()
-                .unwrap_or_else(|_|
-                    Self::parse_with_next_duration(args_iter, &ERR_INVALID_TIMEOUT)
-                );
-        } else if let Some(strip) = arg.strip_prefix("--timeout") {
-            duration_str = strip
-                .parse()
-                .unwrap_or_else(|_|
-                    Self::parse_with_next_duration(args_iter, &ERR_INVALID_TIMEOUT)
-                );
+    fn handle_buckets(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--buckets") {
+            let buckets = Self::parse_usize_value(strip, args_iter, ConfigError::InvalidBuckets)?;
+            if buckets == 0 {
+                return Err(ConfigError::InvalidBuckets(buckets.to_string()));
+            }
+            parsed_config.buckets = buckets;
+            Ok(true)
         } else {
-            eprintln!("{}", ERR_INVALID_TIMEOUT);
-            std::process::exit(1);
+            Ok(false)
         }
-        parsed_config.timeout = Self::parse_duration_string(&duration_str, &ERR_INVALID_TIMEOUT);
-        if parsed_config.timeout.as_secs() == 0 {
-            eprintln!("{}", ERR_INVALID_TIMEOUT);
-            std::process::exit(1);
+    }
+
+    fn handle_hist_max(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--hist-max") {
+            let value = Self::parse_string_value(strip, args_iter, ConfigError::InvalidHistMax)?;
+            let hist_max = value
+                .parse::<f64>()
+                .ok()
+                .filter(|hist_max| *hist_max > 0.0)
+                .ok_or(ConfigError::InvalidHistMax(value))?;
+            parsed_config.hist_max = Some(hist_max);
+            Ok(true)
+        } else {
+            Ok(false)
         }
     }
 
-    fn parse_connection_timeout(
+    fn handle_sla(
         parsed_config: &mut Config,
         arg: &str,
         args_iter: &mut Skip<Iter<String>>
-    ) {
-        let duration_str: String;
-        if let Some(strip) = arg.strip_prefix("-C") {
-            duration_str = strip
-                .parse()
-                .unwrap_or_else(|_|
-                    Self::parse_with_next_duration(args_iter, &ERR_INVALID_CONNECTION_TIMEOUT)
-                );
-        } else if let Some(strip) = arg.strip_prefix("--connection-timeout") {
-            duration_str = strip
-                .parse()
-                .unwrap_or_else(|_|
-                    Self::parse_with_next_duration(args_iter, &ERR_INVALID_CONNECTION_TIMEOUT)
-                );
+    ) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--sla") {
+            let value = Self::parse_string_value(strip, args_iter, ConfigError::InvalidSla)?;
+            let sla = value
+                .parse::<f64>()
+                .ok()
+                .filter(|sla| *sla > 0.0)
+                .ok_or(ConfigError::InvalidSla(value))?;
+            parsed_config.sla = Some(sla);
+            Ok(true)
         } else {
-            eprintln!("{}", ERR_INVALID_CONNECTION_TIMEOUT);
-            std::process::exit(1);
+            Ok(false)
         }
-        parsed_config.connection_timeout = Self::parse_duration_string(
-            &duration_str,
-            &ERR_INVALID_CONNECTION_TIMEOUT
-        );
-        if parsed_config.connection_timeout.as_secs() == 0 {
-            eprintln!("{}", ERR_INVALID_CONNECTION_TIMEOUT);
-            std::process::exit(1);
+    }
+
+    fn handle_min_samples(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--min-samples") {
+            parsed_config.min_samples = Self::parse_usize_value(
+                strip,
+                args_iter,
+                ConfigError::InvalidMinSamples
+            )?;
+            Ok(true)
+        } else {
+            Ok(false)
         }
     }
 
-    // Parse the duration value
-    fn parse_with_next_duration(args_iter: &mut Skip<Iter<String>>, error_msg: &str) -> String {
-        args_iter
-            .next()
-            .and_then(|next| next.parse().ok())
-            .unwrap_or_else(|| {
-                eprintln!("{}", error_msg);
-                std::process::exit(1);
+    fn handle_percentiles(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        let strip = if let Some(strip) = Self::long_flag(arg, "--percentiles") {
+            strip
+        } else {
+            return Ok(false);
+        };
+        let list = Self::parse_string_value(strip, args_iter, ConfigError::InvalidPercentiles)?;
+
+        parsed_config.percentiles = list
+            .split(',')
+            .map(|value| {
+                match value.trim().parse::<f64>() {
+                    Ok(p) if (0.0..=100.0).contains(&p) => Ok(p),
+                    _ => Err(ConfigError::InvalidPercentiles(value.to_string())),
+                }
             })
+            .collect::<Result<Vec<f64>, ConfigError>>()?;
+        Ok(true)
     }
 
-    // Parses the duration string and returns Duration struct
-    fn parse_duration_string(duration_str: &str, error_msg: &str) -> Duration {
-        // nothing specified after -d or --duration
-        if duration_str.len() == 0 {
-            eprintln!("{}", error_msg);
-            std::process::exit(1);
+    fn handle_cert_expiry(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--check-cert-expiry") {
+            parsed_config.cert_expiry_days = Some(
+                Self::parse_usize_value(strip, args_iter, ConfigError::InvalidCertExpiry)?
+            );
+            Ok(true)
+        } else {
+            Ok(false)
         }
+    }
 
-        // if no unit is provided use seconds "s"
-        let duration_str: String = if
-            duration_str.ends_with("s") ||
-            duration_str.ends_with("m") ||
-            duration_str.ends_with("h")
-        {
-            duration_str.to_string()
+    fn handle_record(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--record") {
+            parsed_config.record = Some(
+                Self::parse_string_value(strip, args_iter, ConfigError::InvalidRecord)?
+            );
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn handle_out_file(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--out-file") {
+            parsed_config.out_file = Some(
+                Self::parse_string_value(strip, args_iter, ConfigError::InvalidOutFile)?
+            );
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn handle_timeline(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--timeline") {
+            parsed_config.timeline = Some(
+                Self::parse_string_value(strip, args_iter, ConfigError::InvalidTimeline)?
+            );
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn handle_raw_csv(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--raw-csv") {
+            parsed_config.raw_csv = Some(
+                Self::parse_string_value(strip, args_iter, ConfigError::InvalidRawCsv)?
+            );
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn handle_method(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if Self::short_flag(arg, "-X").is_some() || Self::long_flag(arg, "--method").is_some() {
+            Self::parse_method(parsed_config, arg, args_iter)?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn handle_header(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if Self::short_flag(arg, "-H").is_some() || Self::long_flag(arg, "--header").is_some() {
+            Self::parse_header(parsed_config, arg, args_iter)?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn handle_auth(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        let authorization = if let Some(strip) = Self::long_flag(arg, "--basic-auth") {
+            // resolved before encoding, the encoded value hides any ${VAR} reference
+            let credentials = Self::expand_env_vars(
+                &Self::parse_string_value(strip, args_iter, ConfigError::InvalidBasicAuth)?
+            )?;
+            if !credentials.contains(':') {
+                return Err(ConfigError::InvalidBasicAuth(credentials));
+            }
+            format!("Basic {}", openssl::base64::encode_block(credentials.as_bytes()))
+        } else if let Some(strip) = Self::long_flag(arg, "--bearer") {
+            let token = Self::parse_string_value(strip, args_iter, ConfigError::InvalidBearer)?;
+            if token.is_empty() || HeaderValue::from_str(&token).is_err() {
+                return Err(ConfigError::InvalidBearer(token));
+            }
+            format!("Bearer {}", token)
+        } else {
+            return Ok(false);
+        };
+
+        if parsed_config.authorization.is_some() {
+            return Err(ConfigError::AuthTwice);
+        }
+        parsed_config.authorization = Some(authorization);
+        Ok(true)
+    }
+
+    fn handle_cookies(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if arg == "--cookies" {
+            parsed_config.cookies = true;
+            Ok(true)
+        } else if let Some(strip) = Self::long_flag(arg, "--cookie") {
+            let cookie = Self::parse_string_value(strip, args_iter, ConfigError::InvalidCookie)?;
+            let (name, value) = cookie
+                .split_once('=')
+                .map(|(name, value)| (name.trim(), value.trim()))
+                .filter(|(name, value)| {
+                    !name.is_empty() && isahc::cookies::Cookie::builder(*name, *value).build().is_ok()
+                })
+                .ok_or_else(|| ConfigError::InvalidCookie(cookie.clone()))?;
+            parsed_config.seed_cookies.push((name.to_string(), value.to_string()));
+            parsed_config.cookies = true;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn handle_body(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        let body = if let Some(strip) = Self::long_flag(arg, "--body-file") {
+            let path = Self::parse_string_value(strip, args_iter, ConfigError::InvalidBodyFile)?;
+            std::fs::read(&path).map_err(|_| ConfigError::InvalidBodyFile(path))?
+        } else if let Some(strip) = Self::long_flag(arg, "--body") {
+            Self::parse_string_value(strip, args_iter, ConfigError::InvalidBody)?.into_bytes()
+        } else {
+            return Ok(false);
+        };
+
+        if parsed_config.body.is_some() {
+            return Err(ConfigError::BodyTwice);
+        }
+        parsed_config.body = Some(body);
+        Ok(true)
+    }
+
+    fn handle_graphql(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--graphql-variables") {
+            parsed_config.graphql_variables = Some(
+                Self::parse_string_value(strip, args_iter, ConfigError::InvalidGraphqlVariables)?
+            );
+            Ok(true)
+        } else if let Some(strip) = Self::long_flag(arg, "--graphql-file") {
+            let path = Self::parse_string_value(strip, args_iter, ConfigError::InvalidGraphqlFile)?;
+            let query = std::fs::read_to_string(&path).map_err(|_| ConfigError::InvalidGraphqlFile(path))?;
+            Self::set_graphql_query(parsed_config, query)?;
+            Ok(true)
+        } else if let Some(strip) = Self::long_flag(arg, "--graphql") {
+            let query = Self::parse_string_value(strip, args_iter, ConfigError::InvalidGraphql)?;
+            Self::set_graphql_query(parsed_config, query)?;
+            Ok(true)
         } else {
-            format!("{}s", duration_str)
+            Ok(false)
+        }
+    }
+
+    fn set_graphql_query(parsed_config: &mut Config, query: String) -> Result<(), ConfigError> {
+        if parsed_config.graphql_query.is_some() {
+            return Err(ConfigError::GraphqlQueryTwice);
+        }
+        if query.trim().is_empty() {
+            return Err(ConfigError::InvalidGraphql(query));
+        }
+        parsed_config.graphql_query = Some(query);
+        Ok(())
+    }
+
+    // resolve ${VAR} references in the headers and body (keeps secrets out of argv)
+    fn substitute_env_vars(parsed_config: &mut Config) -> Result<(), ConfigError> {
+        for (name, value) in parsed_config.headers.iter_mut() {
+            *name = Self::expand_env_vars(name)?;
+            *value = Self::expand_env_vars(value)?;
+        }
+        // binary bodies are sent as is
+        if let Some(body) = &parsed_config.body {
+            if let Ok(text) = std::str::from_utf8(body) {
+                if text.contains("${") {
+                    parsed_config.body = Some(Self::expand_env_vars(text)?.into_bytes());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // replace each ${VAR} with its value, an unterminated ${ is kept literally
+    fn expand_env_vars(value: &str) -> Result<String, ConfigError> {
+        let mut expanded = String::with_capacity(value.len());
+        let mut rest = value;
+        while let Some(start) = rest.find("${") {
+            let Some(len) = rest[start + 2..].find('}') else {
+                break;
+            };
+            let var = &rest[start + 2..start + 2 + len];
+            let var_value = std::env::var(var).map_err(|_| ConfigError::UnsetEnvVar(var.to_string()))?;
+            expanded.push_str(&rest[..start]);
+            expanded.push_str(&var_value);
+            rest = &rest[start + 2 + len + 1..];
+        }
+        expanded.push_str(rest);
+        Ok(expanded)
+    }
+
+    // wrap the query in the standard {"query": ..., "variables": ...} envelope
+    fn build_graphql_body(parsed_config: &mut Config) -> Result<(), ConfigError> {
+        let Some(query) = &parsed_config.graphql_query else {
+            return Ok(());
+        };
+        let variables = match &parsed_config.graphql_variables {
+            Some(variables) =>
+                serde_json
+                    ::from_str::<serde_json::Value>(variables)
+                    .ok()
+                    .filter(|value| value.is_object())
+                    .ok_or_else(|| ConfigError::InvalidGraphqlVariables(variables.clone()))?,
+            None => serde_json::json!({}),
         };
+        let envelope = serde_json::json!({ "query": query, "variables": variables });
+        parsed_config.graphql_body = Some(envelope.to_string().into_bytes());
+        Ok(())
+    }
+
+    fn handle_expect(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--expect-status") {
+            let codes = Self::parse_string_value(strip, args_iter, ConfigError::InvalidExpectStatus)?;
+            parsed_config.expect_status = codes
+                .split(',')
+                .map(|code| code.trim().parse::<u16>().ok().filter(|code| (100..600).contains(code)))
+                .collect::<Option<Vec<u16>>>()
+                .ok_or_else(|| ConfigError::InvalidExpectStatus(codes.clone()))?;
+            Ok(true)
+        } else if let Some(strip) = Self::long_flag(arg, "--expect-substring") {
+            let substring = Self::parse_string_value(strip, args_iter, ConfigError::InvalidExpectSubstring)?;
+            if substring.is_empty() {
+                return Err(ConfigError::InvalidExpectSubstring(substring));
+            }
+            parsed_config.expect_substring = Some(substring);
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn handle_form(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--form-file") {
+            let field = Self::parse_string_value(strip, args_iter, ConfigError::InvalidFormFile)?;
+            let (name, path) = field
+                .split_once("=@")
+                .filter(|(name, path)| !name.is_empty() && std::path::Path::new(path).is_file())
+                .ok_or_else(|| ConfigError::InvalidFormFile(field.clone()))?;
+            parsed_config.form_files.push((name.to_string(), path.to_string()));
+            Ok(true)
+        } else if let Some(strip) = Self::long_flag(arg, "--form") {
+            let field = Self::parse_string_value(strip, args_iter, ConfigError::InvalidForm)?;
+            let (name, value) = field
+                .split_once('=')
+                .filter(|(name, _)| !name.is_empty())
+                .ok_or_else(|| ConfigError::InvalidForm(field.clone()))?;
+            parsed_config.form_fields.push((name.to_string(), value.to_string()));
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
 
-        // split into value and unit for (s, m, h)
-        let (value_str, unit) = duration_str.split_at(duration_str.len() - 1);
-        let value: u64 = value_str.parse().unwrap_or_else(|_| {
-            eprintln!("{}", error_msg);
-            std::process::exit(1);
+    // urlencoded body from the --form fields, or multipart once a file is uploaded
+    fn build_form_body(parsed_config: &mut Config) -> Result<(), ConfigError> {
+        if parsed_config.form_files.is_empty() {
+            if !parsed_config.form_fields.is_empty() {
+                let encoded = url::form_urlencoded::Serializer
+                    ::new(String::new())
+                    .extend_pairs(&parsed_config.form_fields)
+                    .finish();
+                parsed_config.form_body = Some(FormBody {
+                    content_type: "application/x-www-form-urlencoded".to_string(),
+                    bytes: encoded.into_bytes(),
+                });
+            }
+            return Ok(());
+        }
+
+        let boundary = format!("benchener-{:016x}", rand::random::<u64>());
+        let mut bytes = Vec::new();
+        for (name, value) in &parsed_config.form_fields {
+            bytes.extend_from_slice(
+                format!(
+                    "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                    boundary,
+                    name,
+                    value
+                ).as_bytes()
+            );
+        }
+        for (name, path) in &parsed_config.form_files {
+            // read once here, every request uploads the same bytes
+            let content = std::fs::read(path).map_err(|_| ConfigError::InvalidFormFile(path.clone()))?;
+            let file_name = std::path::Path
+                ::new(path)
+                .file_name()
+                .map_or(path.clone(), |name| name.to_string_lossy().to_string());
+            bytes.extend_from_slice(
+                format!(
+                    "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: application/octet-stream\r\n\r\n",
+                    boundary,
+                    name,
+                    file_name
+                ).as_bytes()
+            );
+            bytes.extend_from_slice(&content);
+            bytes.extend_from_slice(b"\r\n");
+        }
+        bytes.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+
+        parsed_config.form_body = Some(FormBody {
+            content_type: format!("multipart/form-data; boundary={}", boundary),
+            bytes,
         });
+        Ok(())
+    }
+
+    fn handle_output(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        let strip = if let Some(strip) = Self::short_flag(arg, "-o") {
+            strip
+        } else if let Some(strip) = Self::long_flag(arg, "--output") {
+            strip
+        } else {
+            return Ok(false);
+        };
+        let format = Self::parse_string_value(strip, args_iter, ConfigError::InvalidOutput)?;
+
+        parsed_config.output = match format.as_str() {
+            "text" => OutputFormat::Text,
+            "breakdown" => OutputFormat::Breakdown,
+            "json" => OutputFormat::Json,
+            "prometheus" => OutputFormat::Prometheus,
+            "openmetrics" => OutputFormat::OpenMetrics,
+            "markdown" => OutputFormat::Markdown,
+            _ => {
+                return Err(ConfigError::InvalidOutput(format));
+            }
+        };
+        Ok(true)
+    }
+
+    fn handle_latency_unit(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        let strip = if let Some(strip) = Self::long_flag(arg, "--latency-unit") {
+            strip
+        } else {
+            return Ok(false);
+        };
+        let unit = Self::parse_string_value(strip, args_iter, ConfigError::InvalidLatencyUnit)?;
+
+        parsed_config.latency_unit = match unit.as_str() {
+            "auto" => LatencyUnit::Auto,
+            "us" => LatencyUnit::Micros,
+            "ms" => LatencyUnit::Millis,
+            "s" => LatencyUnit::Secs,
+            _ => {
+                return Err(ConfigError::InvalidLatencyUnit(unit));
+            }
+        };
+        Ok(true)
+    }
+
+    fn handle_summarize(parsed_config: &mut Config, arg: &str) -> bool {
+        if arg == "-s" {
+            parsed_config.summarize = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn handle_insecure(parsed_config: &mut Config, arg: &str) -> bool {
+        if arg == "-k" || arg == "--insecure" {
+            parsed_config.insecure = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn handle_follow_redirects(parsed_config: &mut Config, arg: &str) -> bool {
+        if arg == "-L" || arg == "--follow-redirects" {
+            parsed_config.follow_redirects = true;
+            true
+        } else {
+            false
+        }
+    }
 
-        match unit {
-            "s" => Duration::from_secs(value),
-            "m" => Duration::from_secs(value * 60),
-            "h" => Duration::from_secs(value * 60 * 60),
-            _ => Duration::from_secs(value),
+    fn handle_max_redirects(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--max-redirects") {
+            parsed_config.max_redirects = Some(
+                Self::parse_usize_value(strip, args_iter, ConfigError::InvalidMaxRedirects)?
+            );
+            parsed_config.follow_redirects = true;
+            Ok(true)
+        } else {
+            Ok(false)
         }
     }
+
+    fn handle_restrict_redirects(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        let Some(strip) = Self::long_flag(arg, "--restrict-redirects") else {
+            return Ok(false);
+        };
+        let scope = Self::parse_string_value(strip, args_iter, ConfigError::InvalidRestrictRedirects)?;
+
+        parsed_config.restrict_redirects = match scope.as_str() {
+            "same-host" => RedirectScope::SameHost,
+            "same-domain" => RedirectScope::SameDomain,
+            "any" => RedirectScope::Any,
+            _ => {
+                return Err(ConfigError::InvalidRestrictRedirects(scope));
+            }
+        };
+        Ok(true)
+    }
+
+    fn handle_http_version(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        let strip = if let Some(strip) = Self::long_flag(arg, "--http-version") {
+            strip
+        } else {
+            return Ok(false);
+        };
+        let version = Self::parse_string_value(strip, args_iter, ConfigError::InvalidHttpVersion)?;
+
+        parsed_config.http_version = Some(match version.as_str() {
+            "1.1" => HttpVersion::Http11,
+            "2" | "2.0" => HttpVersion::Http2,
+            _ => {
+                return Err(ConfigError::InvalidHttpVersion(version));
+            }
+        });
+        Ok(true)
+    }
+
+    fn handle_resolve(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        let Some(strip) = Self::long_flag(arg, "--resolve") else {
+            return Ok(false);
+        };
+        let spec = Self::parse_string_value(strip, args_iter, ConfigError::InvalidResolve)?;
+        // the address is the rest, an IPv6 one has colons of its own (brackets are optional, like with curl)
+        let fields: Vec<&str> = spec.splitn(3, ':').collect();
+        let [host, port, address] = fields[..] else {
+            return Err(ConfigError::InvalidResolve(spec));
+        };
+        let address = address.trim_start_matches('[').trim_end_matches(']');
+        let (false, Ok(port), Ok(address)) = (host.is_empty(), port.parse::<u16>(), address.parse::<IpAddr>()) else {
+            return Err(ConfigError::InvalidResolve(spec));
+        };
+
+        parsed_config.resolve.push((host.to_ascii_lowercase(), port, address)); // url hosts are lowercased too
+        Ok(true)
+    }
+
+    fn handle_no_decompress(parsed_config: &mut Config, arg: &str) -> bool {
+        if arg == "--no-decompress" {
+            parsed_config.no_decompress = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn handle_no_keepalive(parsed_config: &mut Config, arg: &str) -> bool {
+        if arg == "--no-keepalive" {
+            parsed_config.no_keepalive = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn handle_human_stderr(parsed_config: &mut Config, arg: &str) -> bool {
+        if arg == "--human-stderr" {
+            parsed_config.human_stderr = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn handle_strict(parsed_config: &mut Config, arg: &str) -> bool {
+        if arg == "--strict" {
+            parsed_config.strict = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn handle_fail_if(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--fail-if") {
+            let expression = Self::parse_string_value(strip, args_iter, ConfigError::InvalidFailIf)?;
+            let condition = Self::parse_fail_condition(&expression).ok_or_else(||
+                ConfigError::InvalidFailIf(expression.clone())
+            )?;
+            parsed_config.fail_if.push(condition);
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    // "<metric><comparison><threshold>" e.g "p99>200ms", "error_rate>=1%", "rps<500"
+    fn parse_fail_condition(expression: &str) -> Option<FailCondition> {
+        // two character comparisons first, ">" is a prefix of ">="
+        let (metric, comparison, threshold) = [
+            (">=", Comparison::GreaterOrEqual),
+            ("<=", Comparison::LessOrEqual),
+            (">", Comparison::Greater),
+            ("<", Comparison::Less),
+        ]
+            .into_iter()
+            .find_map(|(operator, comparison)| {
+                expression
+                    .split_once(operator)
+                    .map(|(metric, threshold)| (metric.trim().to_lowercase(), comparison, threshold.trim()))
+            })?;
+
+        let metric = match metric.as_str() {
+            "avg" => FailMetric::AvgLatency,
+            "max" => FailMetric::MaxLatency,
+            "error_rate" => FailMetric::ErrorRate,
+            "rps" => FailMetric::RequestsPerSec,
+            "errors" => FailMetric::Errors,
+            "non_2xx" => FailMetric::Non2xx,
+            _ => {
+                let percentile = metric.strip_prefix('p')?.parse::<f64>().ok()?;
+                if !(0.0..=100.0).contains(&percentile) {
+                    return None;
+                }
+                FailMetric::Percentile(percentile)
+            }
+        };
+
+        // latencies take ms (the default) or s, the error rate an optional %
+        let threshold = match metric {
+            FailMetric::Percentile(_) | FailMetric::AvgLatency | FailMetric::MaxLatency => {
+                if let Some(millis) = threshold.strip_suffix("ms") {
+                    millis.parse::<f64>().ok()?
+                } else if let Some(secs) = threshold.strip_suffix('s') {
+                    secs.parse::<f64>().ok()? * 1000.0
+                } else {
+                    threshold.parse::<f64>().ok()?
+                }
+            }
+            FailMetric::ErrorRate => threshold.trim_end_matches('%').parse::<f64>().ok()?,
+            _ => threshold.parse::<f64>().ok()?,
+        };
+        if !threshold.is_finite() {
+            return None;
+        }
+
+        Some(FailCondition {
+            expression: expression.to_string(),
+            metric,
+            comparison,
+            threshold,
+        })
+    }
+
+    fn handle_stage(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        let Some(strip) = Self::long_flag(arg, "--stage") else {
+            return Ok(false);
+        };
+        let spec = Self::parse_string_value(strip, args_iter, ConfigError::InvalidStage)?;
+        let Some((duration, concurrency)) = spec
+            .split_once(":c=")
+            .and_then(|(duration, concurrency)| Some((duration, concurrency.parse::<usize>().ok()?)))
+            .filter(|(_, concurrency)| *concurrency > 0) else {
+            return Err(ConfigError::InvalidStage(spec));
+        };
+        let duration = Self::parse_duration_string(duration, ConfigError::InvalidStage)?;
+        if duration.is_zero() {
+            return Err(ConfigError::InvalidStage(spec));
+        }
+
+        parsed_config.stages.push(Stage { spec, duration, concurrency });
+        Ok(true)
+    }
+
+    fn handle_ramp(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        let Some(strip) = Self::long_flag(arg, "--ramp") else {
+            return Ok(false);
+        };
+        let spec = Self::parse_string_value(strip, args_iter, ConfigError::InvalidRamp)?;
+        let fields: Vec<&str> = spec.splitn(3, ':').collect();
+        let [start, end, duration] = fields[..] else {
+            return Err(ConfigError::InvalidRamp(spec));
+        };
+        let (Ok(start), Ok(end)) = (start.parse::<usize>(), end.parse::<usize>()) else {
+            return Err(ConfigError::InvalidRamp(spec));
+        };
+        let Ok(duration) = Self::parse_duration_string(duration, ConfigError::InvalidRamp) else {
+            return Err(ConfigError::InvalidRamp(spec));
+        };
+        if end == 0 || duration.is_zero() {
+            return Err(ConfigError::InvalidRamp(spec));
+        }
+
+        parsed_config.ramp = Some(Ramp { start, end, duration });
+        Ok(true)
+    }
+
+    fn handle_cache_comparison(parsed_config: &mut Config, arg: &str) -> bool {
+        if arg == "--cache-comparison" {
+            parsed_config.cache_comparison = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn handle_yes(parsed_config: &mut Config, arg: &str) -> bool {
+        if arg == "-y" || arg == "--yes" {
+            parsed_config.yes = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn handle_correct_co(parsed_config: &mut Config, arg: &str) -> bool {
+        if arg == "--correct-co" {
+            parsed_config.correct_co = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn handle_quiet(parsed_config: &mut Config, arg: &str) -> bool {
+        if arg == "-q" || arg == "--quiet" {
+            parsed_config.quiet = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn handle_live(parsed_config: &mut Config, arg: &str) -> bool {
+        if arg == "--live" {
+            parsed_config.live = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn handle_server_timing(parsed_config: &mut Config, arg: &str) -> bool {
+        if arg == "--server-timing" {
+            parsed_config.server_timing = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn handle_expect_continue(parsed_config: &mut Config, arg: &str) -> bool {
+        if arg == "--expect-continue" {
+            parsed_config.expect_continue = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn handle_tokio_console(parsed_config: &mut Config, arg: &str) -> Result<bool, ConfigError> {
+        if arg != "--tokio-console" {
+            return Ok(false);
+        }
+        if !cfg!(feature = "tokio-console") {
+            return Err(ConfigError::TokioConsoleUnavailable);
+        }
+        parsed_config.tokio_console = true;
+        Ok(true)
+    }
+
+    fn handle_dry_run(parsed_config: &mut Config, arg: &str) -> bool {
+        if arg == "--dry-run" {
+            parsed_config.dry_run = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn handle_verify(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        let Some(strip) = Self::long_flag(arg, "--verify") else {
+            return Ok(false);
+        };
+        let path = Self::parse_string_value(strip, args_iter, ConfigError::InvalidVerify)?;
+        if path.is_empty() {
+            return Err(ConfigError::InvalidVerify(path));
+        }
+        parsed_config.verify = Some(path);
+        Ok(true)
+    }
+
+    fn handle_confirm_thresholds(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--confirm-requests") {
+            parsed_config.confirm_requests = Self::parse_usize_value(
+                strip,
+                args_iter,
+                ConfigError::InvalidConfirmRequests
+            )?;
+            Ok(true)
+        } else if let Some(strip) = Self::long_flag(arg, "--confirm-concurrency") {
+            parsed_config.confirm_concurrency = Self::parse_usize_value(
+                strip,
+                args_iter,
+                ConfigError::InvalidConfirmConcurrency
+            )?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn handle_url(
+        parsed_config: &mut Config,
+        arg: &str,
+        is_url_set: &mut bool
+    ) -> Result<bool, ConfigError> {
+        if !*is_url_set && !arg.starts_with("-") {
+            Self::parse_url(parsed_config, arg)?;
+            *is_url_set = true;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    // already applied before the other flags (see find_config_file), only skip over its value
+    fn handle_config(arg: &str, args_iter: &mut Skip<Iter<String>>) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--config") {
+            Self::parse_string_value(strip, args_iter, ConfigError::InvalidConfigFile)?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    // path given with --config (if any)
+    fn find_config_file(args: &[String]) -> Result<Option<String>, ConfigError> {
+        let mut args_iter = args.iter().skip(1);
+        while let Some(arg) = args_iter.next() {
+            if let Some(strip) = Self::long_flag(arg, "--config") {
+                return Self::parse_string_value(strip, &mut args_iter, ConfigError::InvalidConfigFile).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    fn read_config_file(path: &str) -> Result<FileConfig, ConfigError> {
+        let contents = std::fs
+            ::read_to_string(path)
+            .map_err(|_| ConfigError::InvalidConfigFile(path.to_string()))?;
+        // unknown keys are rejected too (deny_unknown_fields), the error names the offending key
+        toml::from_str(&contents).map_err(|e| ConfigError::InvalidConfig {
+            path: path.to_string(),
+            reason: e.to_string().trim_end().to_string(),
+        })
+    }
+
+    // same validation as the matching flags
+    fn apply_config_file(
+        parsed_config: &mut Config,
+        file_config: FileConfig,
+        req_count_test_provided: &mut bool,
+        concurrency_provided: &mut bool
+    ) -> Result<(), ConfigError> {
+        let positive = |value: usize, error: fn(String) -> ConfigError| {
+            if value == 0 { Err(error(value.to_string())) } else { Ok(value) }
+        };
+        let duration = |value: &str, error: fn(String) -> ConfigError| {
+            let duration = Self::parse_duration_string(value, error)?;
+            if duration.is_zero() { Err(error(value.to_string())) } else { Ok(duration) }
+        };
+
+        if let Some(requests) = file_config.requests {
+            parsed_config.requests = positive(requests, ConfigError::InvalidRequests)?;
+            *req_count_test_provided = true;
+        }
+        if let Some(value) = &file_config.duration {
+            parsed_config.duration = duration(value, ConfigError::InvalidDuration)?;
+            parsed_config.test_type = if *req_count_test_provided {
+                TestType::Both
+            } else {
+                TestType::Duration
+            };
+        }
+        if let Some(concurrency) = file_config.concurrency {
+            parsed_config.concurrency = positive(concurrency, ConfigError::InvalidConcurrency)?;
+            *concurrency_provided = true;
+        }
+        if let Some(threads) = file_config.threads {
+            parsed_config.threads = positive(threads, ConfigError::InvalidThreads)?;
+        }
+        if let Some(value) = &file_config.timeout {
+            parsed_config.timeout = duration(value, ConfigError::InvalidTimeout)?;
+        }
+        if let Some(value) = &file_config.connection_timeout {
+            parsed_config.connection_timeout = duration(value, ConfigError::InvalidConnectionTimeout)?;
+        }
+        if let Some(method) = &file_config.method {
+            parsed_config.method = Self::method_from_name(method)?;
+        }
+        for header in file_config.headers.unwrap_or_default() {
+            parsed_config.headers.push(Self::split_header(&header)?);
+        }
+        Ok(())
+    }
+
+    fn parse_url(parsed_config: &mut Config, url: &str) -> Result<(), ConfigError> {
+        Self::validate_url(url)?;
+        parsed_config.url = url.to_string();
+        Ok(())
+    }
+
+    fn validate_url(url: &str) -> Result<(), ConfigError> {
+        // Check if the url is correct
+        let parsed = Url::parse(url).map_err(|_| ConfigError::InvalidUrl(url.to_string()))?;
+        if parsed.scheme() != "http" && parsed.scheme() != "https" {
+            return Err(ConfigError::UnsupportedScheme(url.to_string()));
+        }
+        // Url::parse turns http:///path and http:/host into http://path/ and http://host/, take them as missing a host
+        let authority = url.split_once(':').map_or("", |(_, rest)| rest);
+        if !authority.starts_with("//") || authority[2..].starts_with('/') || parsed.host_str().is_none_or(str::is_empty) {
+            return Err(ConfigError::InvalidUrl(url.to_string()));
+        }
+        Ok(())
+    }
+
+    // one url per line, empty lines and lines starting with # are skipped
+    fn handle_urls_file(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        let strip = if let Some(strip) = Self::long_flag(arg, "--urls-file") {
+            strip
+        } else {
+            return Ok(false);
+        };
+        let path = Self::parse_string_value(strip, args_iter, ConfigError::InvalidUrlsFile)?;
+        let content = std::fs::read_to_string(&path).map_err(|_| ConfigError::InvalidUrlsFile(path.clone()))?;
+
+        let urls: Vec<String> = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect();
+        if urls.is_empty() {
+            return Err(ConfigError::EmptyUrlsFile(path));
+        }
+        for url in &urls {
+            Self::validate_url(url)?;
+        }
+        parsed_config.urls.extend(urls);
+        Ok(true)
+    }
+
+    fn handle_urls_sample(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        if let Some(strip) = Self::long_flag(arg, "--urls-sample") {
+            let sample = Self::parse_usize_value(strip, args_iter, ConfigError::InvalidUrlsSample)?;
+            if sample == 0 {
+                return Err(ConfigError::InvalidUrlsSample(sample.to_string()));
+            }
+            parsed_config.urls_sample = Some(sample);
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    // one request per line, empty lines and lines starting with # are skipped
+    fn handle_script(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<bool, ConfigError> {
+        let strip = if let Some(strip) = Self::long_flag(arg, "--script") {
+            strip
+        } else {
+            return Ok(false);
+        };
+        let path = Self::parse_string_value(strip, args_iter, ConfigError::InvalidScript)?;
+        let content = std::fs::read_to_string(&path).map_err(|_| ConfigError::InvalidScript(path.clone()))?;
+        let dir = Path::new(&path).parent().unwrap_or(Path::new("")); // body files are relative to the script
+
+        let lines = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+        for line in lines {
+            parsed_config.script.push(Self::parse_request_spec(line, dir)?);
+        }
+        if parsed_config.script.is_empty() {
+            return Err(ConfigError::EmptyScript(path));
+        }
+        Ok(true)
+    }
+
+    fn parse_request_spec(line: &str, dir: &Path) -> Result<RequestSpec, ConfigError> {
+        let invalid = || ConfigError::InvalidScriptLine(line.to_string());
+        let mut fields: Vec<&str> = line.split_whitespace().collect();
+
+        // the weight is optional, a method never starts with a digit
+        let weight = match fields.first() {
+            Some(first) if first.starts_with(|c: char| c.is_ascii_digit()) => {
+                let weight = first
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|weight| *weight > 0)
+                    .ok_or_else(invalid)?;
+                fields.remove(0);
+                weight
+            }
+            _ => 1,
+        };
+        let (method, url, body_file) = match fields[..] {
+            [method, url] => (method, url, None),
+            [method, url, body_file] => (method, url, Some(body_file)),
+            _ => {
+                return Err(invalid());
+            }
+        };
+        let method = Self::method_from_name(method).map_err(|_| invalid())?;
+        Self::validate_url(url)?;
+        let body = match body_file {
+            Some(body_file) => {
+                let path = dir.join(body_file);
+                Some(std::fs::read(&path).map_err(|_| ConfigError::InvalidBodyFile(path.display().to_string()))?)
+            }
+            None => None,
+        };
+        Ok(RequestSpec { method, url: url.to_string(), body, weight })
+    }
+
+    fn parse_method(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<(), ConfigError> {
+        let strip = Self::short_flag(arg, "-X")
+            .or_else(|| Self::long_flag(arg, "--method"))
+            .unwrap_or_default();
+        let method = Self::parse_string_value(strip, args_iter, ConfigError::InvalidMethod)?;
+        parsed_config.method = Self::method_from_name(&method)?;
+        Ok(())
+    }
+
+    fn method_from_name(method: &str) -> Result<Method, ConfigError> {
+        let method = method.to_uppercase();
+        match method.as_str() {
+            "GET" => Ok(Method::GET),
+            "POST" => Ok(Method::POST),
+            "PUT" => Ok(Method::PUT),
+            "DELETE" => Ok(Method::DELETE),
+            "PATCH" => Ok(Method::PATCH),
+            "HEAD" => Ok(Method::HEAD),
+            "OPTIONS" => Ok(Method::OPTIONS),
+            _ => Err(ConfigError::InvalidMethod(method)),
+        }
+    }
+
+    fn parse_header(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<(), ConfigError> {
+        let strip = Self::short_flag(arg, "-H")
+            .or_else(|| Self::long_flag(arg, "--header"))
+            .unwrap_or_default();
+        let header = Self::parse_string_value(strip, args_iter, ConfigError::InvalidHeader)?;
+        parsed_config.headers.push(Self::split_header(&header)?);
+        Ok(())
+    }
+
+    fn split_header(header: &str) -> Result<(String, String), ConfigError> {
+        // split on the first colon, values may contain colons themselves (e.g urls)
+        let (name, value) = header
+            .split_once(':')
+            .map(|(name, value)| (name.trim(), value.trim()))
+            .filter(|(name, value)| {
+                HeaderName::from_bytes(name.as_bytes()).is_ok() &&
+                    HeaderValue::from_str(value).is_ok()
+            })
+            .ok_or_else(|| ConfigError::InvalidHeader(header.to_string()))?;
+        Ok((name.to_string(), value.to_string()))
+    }
+
+    fn parse_requests(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<(), ConfigError> {
+        let strip = Self::short_flag(arg, "-n")
+            .or_else(|| Self::long_flag(arg, "--requests"))
+            .unwrap_or_default();
+        let value = Self::parse_string_value(strip, args_iter, ConfigError::InvalidRequests)?;
+        parsed_config.requests = Self::parse_count(&value)
+            .filter(|requests| *requests > 0)
+            .ok_or(ConfigError::InvalidRequests(value))?;
+        Ok(())
+    }
+
+    // a plain integer, or one with a k / M / G suffix (x1000, x1000000, x1000000000)
+    fn parse_count(value: &str) -> Option<usize> {
+        let (digits, multiplier) = match value.char_indices().last()? {
+            (i, 'k' | 'K') => (&value[..i], 1_000),
+            (i, 'm' | 'M') => (&value[..i], 1_000_000),
+            (i, 'g' | 'G') => (&value[..i], 1_000_000_000),
+            _ => (value, 1),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None; // no signs, spaces or fractions (1.5k)
+        }
+        digits.parse::<usize>().ok()?.checked_mul(multiplier)
+    }
+
+    fn parse_concurrency(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<(), ConfigError> {
+        let strip = Self::short_flag(arg, "-c")
+            .or_else(|| Self::long_flag(arg, "--concurrency"))
+            .unwrap_or_default();
+        parsed_config.concurrency = Self::parse_usize_value(strip, args_iter, ConfigError::InvalidConcurrency)?;
+
+        if parsed_config.concurrency == 0 {
+            return Err(ConfigError::InvalidConcurrency(parsed_config.concurrency.to_string()));
+        }
+        Ok(())
+    }
+
+    fn parse_threads(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<(), ConfigError> {
+        let strip = Self::short_flag(arg, "-t")
+            .or_else(|| Self::long_flag(arg, "--threads"))
+            .unwrap_or_default();
+        parsed_config.threads = Self::parse_usize_value(strip, args_iter, ConfigError::InvalidThreads)?;
+
+        if parsed_config.threads == 0 {
+            return Err(ConfigError::InvalidThreads(parsed_config.threads.to_string()));
+        }
+        Ok(())
+    }
+
+    // number attached to the flag (-n10 / --requests=10) or the next argument (-n 10)
+    fn parse_usize_value(
+        strip: &str,
+        args_iter: &mut Skip<Iter<String>>,
+        error: fn(String) -> ConfigError
+    ) -> Result<usize, ConfigError> {
+        let value = Self::parse_string_value(strip, args_iter, error)?;
+        value.parse().map_err(|_| error(value))
+    }
+
+    // value attached to a long flag (--flag=VALUE), "" when given alone (the value is the next argument),
+    // None for any other argument (so --threads never matches --threadsX or --threads-foo)
+    fn long_flag<'a>(arg: &'a str, flag: &str) -> Option<&'a str> {
+        if arg == flag {
+            Some("")
+        } else {
+            arg.strip_prefix(flag)?.strip_prefix('=')
+        }
+    }
+
+    // value attached to a short flag (-t4), "" when given alone (-t 4)
+    fn short_flag<'a>(arg: &'a str, flag: &str) -> Option<&'a str> {
+        arg.strip_prefix(flag)
+    }
+
+    // value attached to the flag (-fVALUE / --flag=VALUE) or the next argument (--flag VALUE)
+    fn parse_string_value(
+        strip: &str,
+        args_iter: &mut Skip<Iter<String>>,
+        error: fn(String) -> ConfigError
+    ) -> Result<String, ConfigError> {
+        if strip.is_empty() {
+            Self::parse_with_next_string(args_iter, error)
+        } else {
+            Ok(strip.to_string())
+        }
+    }
+
+    // for flags taking a string value (--record file.log)
+    fn parse_with_next_string(
+        args_iter: &mut Skip<Iter<String>>,
+        error: fn(String) -> ConfigError
+    ) -> Result<String, ConfigError> {
+        args_iter.next().cloned().ok_or_else(|| error(String::new()))
+    }
+
+    /* ----Durations ----*/
+    // Parse the duration flag
+    fn parse_duration(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<(), ConfigError> {
+        let strip = Self::short_flag(arg, "-d")
+            .or_else(|| Self::long_flag(arg, "--duration"))
+            .unwrap_or_default();
+        let duration_str = Self::parse_string_value(strip, args_iter, ConfigError::InvalidDuration)?;
+        parsed_config.duration = Self::parse_duration_string(&duration_str, ConfigError::InvalidDuration)?;
+        if parsed_config.duration.is_zero() {
+            return Err(ConfigError::InvalidDuration(duration_str));
+        }
+        Ok(())
+    }
+
+    fn parse_timeout(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<(), ConfigError> {
+        let strip = Self::short_flag(arg, "-T")
+            .or_else(|| Self::long_flag(arg, "--timeout"))
+            .unwrap_or_default();
+        let duration_str = Self::parse_string_value(strip, args_iter, ConfigError::InvalidTimeout)?;
+        parsed_config.timeout = Self::parse_duration_string(&duration_str, ConfigError::InvalidTimeout)?;
+        if parsed_config.timeout.is_zero() {
+            return Err(ConfigError::InvalidTimeout(duration_str));
+        }
+        Ok(())
+    }
+
+    fn parse_connection_timeout(
+        parsed_config: &mut Config,
+        arg: &str,
+        args_iter: &mut Skip<Iter<String>>
+    ) -> Result<(), ConfigError> {
+        let strip = Self::short_flag(arg, "-C")
+            .or_else(|| Self::long_flag(arg, "--connection-timeout"))
+            .unwrap_or_default();
+        let duration_str = Self::parse_string_value(strip, args_iter, ConfigError::InvalidConnectionTimeout)?;
+        parsed_config.connection_timeout = Self::parse_duration_string(
+            &duration_str,
+            ConfigError::InvalidConnectionTimeout
+        )?;
+        if parsed_config.connection_timeout.is_zero() {
+            return Err(ConfigError::InvalidConnectionTimeout(duration_str));
+        }
+        Ok(())
+    }
+
+    // Parses durations like 10s, 500ms, 1.5s or 1h30m (a plain number is in seconds)
+    fn parse_duration_string(
+        duration_str: &str,
+        error: fn(String) -> ConfigError
+    ) -> Result<Duration, ConfigError> {
+        let invalid = || error(duration_str.to_string());
+
+        // nothing specified after -d or --duration
+        if duration_str.is_empty() {
+            return Err(invalid());
+        }
+        if let Ok(secs) = duration_str.parse::<f64>() {
+            return Duration::try_from_secs_f64(secs).map_err(|_| invalid());
+        }
+
+        // sum of <value><unit> parts
+        let mut total = Duration::ZERO;
+        let mut rest = duration_str;
+        while !rest.is_empty() {
+            let is_number = |c: char| c.is_ascii_digit() || c == '.';
+            let (value, tail) = rest.split_at(rest.find(|c: char| !is_number(c)).unwrap_or(rest.len()));
+            let (unit, tail) = tail.split_at(tail.find(is_number).unwrap_or(tail.len()));
+            let value: f64 = value.parse().map_err(|_| invalid())?;
+            let scale = match unit {
+                "ms" => 0.001,
+                "s" => 1.0,
+                "m" => 60.0,
+                "h" => 60.0 * 60.0,
+                _ => {
+                    return Err(invalid());
+                }
+            };
+            total += Duration::try_from_secs_f64(value * scale).map_err(|_| invalid())?;
+            rest = tail;
+        }
+        Ok(total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "http://localhost:8080";
+
+    fn parse(args: &[&str]) -> Result<Config, ConfigError> {
+        let args: Vec<String> = std::iter
+            ::once("benchener")
+            .chain(args.iter().copied())
+            .map(str::to_string)
+            .collect();
+        Config::parse_args(&args)
+    }
+
+    fn parse_err(args: &[&str]) -> ConfigError {
+        parse(args).expect_err("the arguments should be rejected")
+    }
+
+    #[test]
+    fn parses_the_url() {
+        let config = parse(&[URL]).unwrap();
+        assert_eq!(config.url, URL);
+        assert_eq!(config.urls, vec![URL.to_string()]);
+        assert_eq!(parse(&["https://example.com/path"]).unwrap().url, "https://example.com/path");
+    }
+
+    #[test]
+    fn parses_requests() {
+        let config = parse(&["-n", "20", URL]).unwrap();
+        assert_eq!(config.requests, 20);
+        assert_eq!(config.test_type, TestType::RequestCount);
+        assert_eq!(parse(&["--requests=2k", URL]).unwrap().requests, 2000);
+    }
+
+    #[test]
+    fn parses_request_suffixes() {
+        let requests = |value: &str| parse(&["-n", value, URL]).unwrap().requests;
+        assert_eq!(requests("1k"), 1_000);
+        assert_eq!(requests("1K"), 1_000);
+        assert_eq!(requests("2M"), 2_000_000);
+    }
+
+    #[test]
+    fn rejects_invalid_requests() {
+        assert_eq!(parse_err(&["-n", "0", URL]), ConfigError::InvalidRequests("0".to_string()));
+        assert_eq!(parse_err(&["-n", "1.5k", URL]), ConfigError::InvalidRequests("1.5k".to_string()));
+        assert_eq!(parse_err(&[URL, "-n"]), ConfigError::InvalidRequests(String::new()));
+        for invalid in ["10x", "k", "18446744073709551615k"] {
+            assert_eq!(parse_err(&["-n", invalid, URL]), ConfigError::InvalidRequests(invalid.to_string()));
+        }
+    }
+
+    #[test]
+    fn parses_concurrency() {
+        assert_eq!(parse(&["-n", "10", "-c4", URL]).unwrap().concurrency, 4);
+        assert_eq!(parse(&["-n", "10", "--concurrency", "5", URL]).unwrap().concurrency, 5);
+    }
+
+    #[test]
+    fn rejects_invalid_concurrency() {
+        assert_eq!(parse_err(&["-c", "0", URL]), ConfigError::InvalidConcurrency("0".to_string()));
+        assert_eq!(parse_err(&["-cx", URL]), ConfigError::InvalidConcurrency("x".to_string()));
+    }
+
+    #[test]
+    fn rejects_concurrency_above_the_requests() {
+        assert_eq!(parse_err(&["-n", "2", "-c", "5", URL]), ConfigError::InvalidRequestsAndConcurrency);
+    }
+
+    #[test]
+    fn duration_tests_take_any_concurrency() {
+        let config = parse(&["-d", "10s", "-c", "50", URL]).unwrap();
+        assert_eq!((config.test_type, config.concurrency), (TestType::Duration, 50));
+    }
+
+    #[test]
+    fn parses_threads() {
+        assert_eq!(parse(&["--threads=2", URL]).unwrap().threads, 2);
+    }
+
+    #[test]
+    fn rejects_invalid_threads() {
+        assert_eq!(parse_err(&["-t", "-1", URL]), ConfigError::InvalidThreads("-1".to_string()));
+        assert!(matches!(parse_err(&["--max-blocking-threads", "0", URL]), ConfigError::InvalidMaxBlockingThreads(_)));
+    }
+
+    #[test]
+    fn parses_duration() {
+        let config = parse(&["-d", "1m30s", URL]).unwrap();
+        assert_eq!(config.duration, Duration::from_secs(90));
+        assert_eq!(config.test_type, TestType::Duration);
+    }
+
+    #[test]
+    fn duration_with_requests_runs_both() {
+        let config = parse(&["-n", "2k", "-d", "30s", URL]).unwrap();
+        assert_eq!((config.requests, config.duration), (2000, Duration::from_secs(30)));
+        assert_eq!(config.test_type, TestType::Both);
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        assert_eq!(parse_err(&["-d", "10x", URL]), ConfigError::InvalidDuration("10x".to_string()));
+        assert_eq!(parse_err(&["-d", "0s", URL]), ConfigError::InvalidDuration("0s".to_string()));
+        for invalid in ["1h30", "2m30x", "s", "1..5s", "5s-1s", "1m 30s"] {
+            assert_eq!(parse_err(&["-d", invalid, URL]), ConfigError::InvalidDuration(invalid.to_string()));
+        }
+    }
+
+    #[test]
+    fn parses_duration_units() {
+        let duration = |value: &str| parse(&["-d", value, URL]).unwrap().duration;
+        assert_eq!(duration("500ms"), Duration::from_millis(500));
+        assert_eq!(duration("1.5s"), Duration::from_millis(1500));
+        assert_eq!(duration("2m30s"), Duration::from_secs(150));
+        assert_eq!(duration("1h"), Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn rejects_invalid_timeouts() {
+        assert_eq!(parse_err(&["-T", "", URL]), ConfigError::InvalidTimeout(String::new()));
+        assert_eq!(parse_err(&["-C", "abc", URL]), ConfigError::InvalidConnectionTimeout("abc".to_string()));
+    }
+
+    #[test]
+    fn parses_max_duration() {
+        let config = parse(&["-n", "1000", "--max-duration", "2m", URL]).unwrap();
+        assert_eq!(config.max_duration, Some(Duration::from_secs(120)));
+        assert_eq!(parse_err(&["--max-duration", "0s", URL]), ConfigError::InvalidMaxDuration("0s".to_string()));
+    }
+
+    #[test]
+    fn max_duration_only_caps_request_count_tests() {
+        assert_eq!(parse_err(&["--max-duration", "1m", "-d", "10s", URL]), ConfigError::MaxDurationConflict);
+        assert_eq!(parse_err(&["--max-duration", "1m", "--stage", "10s:c=2", URL]), ConfigError::MaxDurationConflict);
+    }
+
+    #[test]
+    fn parses_method() {
+        assert_eq!(parse(&["-X", "post", URL]).unwrap().method, Method::POST);
+        assert_eq!(parse(&[URL]).unwrap().method, Method::GET);
+        assert_eq!(parse_err(&["-X", "brew", URL]), ConfigError::InvalidMethod("BREW".to_string()));
+    }
+
+    #[test]
+    fn rejects_invalid_headers() {
+        assert_eq!(parse_err(&["-H", "NoColon", URL]), ConfigError::InvalidHeader("NoColon".to_string()));
+        assert_eq!(
+            parse_err(&["-H", "X-Token: ${BENCHENER_TEST_UNSET_VAR}", URL]),
+            ConfigError::UnsetEnvVar("BENCHENER_TEST_UNSET_VAR".to_string())
+        );
+    }
+
+    #[test]
+    fn parses_live() {
+        assert!(parse(&["--live", URL]).unwrap().live);
+        assert!(!parse(&[URL]).unwrap().live);
+    }
+
+    #[test]
+    fn expect_continue_needs_a_body() {
+        assert!(parse(&["--expect-continue", "--body", "x", URL]).unwrap().expect_continue);
+        assert!(!parse(&["--body", "x", URL]).unwrap().expect_continue);
+        assert_eq!(parse_err(&["--expect-continue", URL]), ConfigError::ExpectContinueWithoutBody);
+    }
+
+    #[test]
+    fn verify_needs_no_url() {
+        assert_eq!(parse(&["--verify", "report.json"]).unwrap().verify.as_deref(), Some("report.json"));
+        assert_eq!(parse_err(&["--verify="]), ConfigError::InvalidVerify(String::new()));
+    }
+
+    #[test]
+    fn request_ids_default_with_openmetrics() {
+        assert_eq!(parse(&[URL]).unwrap().request_id_header, None);
+        assert_eq!(parse(&["-o", "openmetrics", URL]).unwrap().request_id_header.as_deref(), Some("X-Request-Id"));
+        let config = parse(&["-o", "openmetrics", "--request-id-header", "X-Trace", URL]).unwrap();
+        assert_eq!(config.request_id_header.as_deref(), Some("X-Trace"));
+        assert_eq!(parse_err(&["--request-id-header", "bad name", URL]), ConfigError::InvalidRequestIdHeader("bad name".to_string()));
+    }
+
+    #[test]
+    fn parses_server_timing() {
+        assert!(parse(&["--server-timing", URL]).unwrap().server_timing);
+        assert!(!parse(&[URL]).unwrap().server_timing);
+    }
+
+    #[test]
+    fn parses_name() {
+        assert_eq!(parse(&["--name", "baseline-v1.2", URL]).unwrap().name.as_deref(), Some("baseline-v1.2"));
+        assert_eq!(parse_err(&["--name", " ", URL]), ConfigError::InvalidName(" ".to_string()));
+    }
+
+    #[test]
+    fn parses_output_formats() {
+        assert_eq!(parse(&["-o", "prometheus", URL]).unwrap().output, OutputFormat::Prometheus);
+        assert_eq!(parse(&["-o", "markdown", URL]).unwrap().output, OutputFormat::Markdown);
+        assert_eq!(parse(&["-o", "openmetrics", URL]).unwrap().output, OutputFormat::OpenMetrics);
+        assert_eq!(parse_err(&["-o", "xml", URL]), ConfigError::InvalidOutput("xml".to_string()));
+    }
+
+    #[test]
+    fn human_stderr_needs_a_machine_output() {
+        assert!(parse(&["--human-stderr", "-o", "json", URL]).unwrap().human_stderr);
+        assert_eq!(parse_err(&["--human-stderr", URL]), ConfigError::HumanStderrNeedsMachineOutput);
+        assert_eq!(parse_err(&["--human-stderr", "-o", "breakdown", URL]), ConfigError::HumanStderrNeedsMachineOutput);
+    }
+
+    #[test]
+    fn help_and_version_are_not_failures() {
+        assert_eq!(parse_err(&[]), ConfigError::Help);
+        assert_eq!(parse_err(&["--help"]), ConfigError::Help);
+        assert_eq!(parse_err(&["-v"]), ConfigError::Version);
+    }
+
+    #[test]
+    fn rejects_unknown_arguments() {
+        assert_eq!(parse_err(&[URL, "--bogus"]), ConfigError::UnknownArgument("--bogus".to_string()));
+        assert_eq!(parse_err(&[URL, "--threadsX"]), ConfigError::UnknownArgument("--threadsX".to_string()));
+        assert_eq!(parse_err(&[URL, URL]), ConfigError::UnknownArgument(URL.to_string()));
+    }
+
+    #[test]
+    fn rejects_invalid_connections() {
+        assert_eq!(parse_err(&["--connections=0", URL]), ConfigError::InvalidConnections("0".to_string()));
+    }
+
+    #[test]
+    fn rejects_invalid_pipeline() {
+        assert_eq!(parse_err(&["--pipeline", "0", URL]), ConfigError::InvalidPipeline("0".to_string()));
+        assert_eq!(parse_err(&["--pipeline", "2", URL]), ConfigError::PipelineWithoutConnections);
+    }
+
+    #[test]
+    fn rejects_contradicting_concurrency() {
+        assert_eq!(
+            parse_err(&["--connections", "2", "--pipeline", "2", "-c", "5", "-n", "10", URL]),
+            ConfigError::ContradictingConcurrency { concurrency: 5, connections: 2, pipeline: 2 }
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_rate() {
+        assert_eq!(parse_err(&["--rate", "0", URL]), ConfigError::InvalidRate("0".to_string()));
+    }
+
+    #[test]
+    fn correct_co_requires_a_rate() {
+        assert_eq!(parse_err(&["--correct-co", URL]), ConfigError::CorrectCoWithoutRate);
+    }
+
+    #[test]
+    fn rejects_invalid_buckets() {
+        assert_eq!(parse_err(&["--buckets", "0", URL]), ConfigError::InvalidBuckets("0".to_string()));
+    }
+
+    #[test]
+    fn rejects_invalid_error_limits() {
+        assert_eq!(parse_err(&["--max-errors", "x", URL]), ConfigError::InvalidMaxErrors("x".to_string()));
+        assert_eq!(parse_err(&["--max-error-rate", "100", URL]), ConfigError::InvalidMaxErrorRate("100".to_string()));
+    }
+
+    #[test]
+    fn rejects_invalid_retries() {
+        assert_eq!(parse_err(&["--retries", "-1", URL]), ConfigError::InvalidRetries("-1".to_string()));
+        assert_eq!(parse_err(&["--retry-backoff", "soon", URL]), ConfigError::InvalidRetryBackoff("soon".to_string()));
+    }
+
+    #[test]
+    fn retry_on_5xx_requires_retries() {
+        assert_eq!(parse_err(&["--retry-on-5xx", URL]), ConfigError::RetryOn5xxWithoutRetries);
+    }
+
+    #[test]
+    fn rejects_invalid_min_samples() {
+        assert_eq!(parse_err(&["--min-samples", "x", URL]), ConfigError::InvalidMinSamples("x".to_string()));
+    }
+
+    #[test]
+    fn rejects_invalid_seed() {
+        assert_eq!(parse_err(&["--seed", "x", URL]), ConfigError::InvalidSeed("x".to_string()));
+    }
+
+    #[test]
+    fn restricts_redirects() {
+        assert_eq!(parse(&[URL]).unwrap().restrict_redirects, RedirectScope::SameHost);
+        assert_eq!(parse(&["--restrict-redirects", "any", URL]).unwrap().restrict_redirects, RedirectScope::Any);
+        assert_eq!(
+            parse_err(&["--restrict-redirects=other", URL]),
+            ConfigError::InvalidRestrictRedirects("other".to_string())
+        );
+
+        let url = |url: &str| Url::parse(url).unwrap();
+        let from = url("https://www.example.com/a");
+        assert!(RedirectScope::SameHost.allows(&from, &url("https://www.example.com:443/b")));
+        assert!(!RedirectScope::SameHost.allows(&from, &url("http://www.example.com/b")));
+        assert!(!RedirectScope::SameHost.allows(&from, &url("https://api.example.com/b")));
+        assert!(RedirectScope::SameDomain.allows(&from, &url("http://API.example.com/b")));
+        assert!(!RedirectScope::SameDomain.allows(&from, &url("https://example.org/b")));
+        assert!(!RedirectScope::SameDomain.allows(&url("http://127.0.0.1/"), &url("http://127.0.0.2/")));
+        assert!(RedirectScope::Any.allows(&from, &url("https://example.org/b")));
+    }
+
+    #[test]
+    fn rejects_invalid_max_redirects() {
+        assert!(matches!(parse_err(&["--max-redirects", "x", URL]), ConfigError::InvalidMaxRedirects(_)));
+    }
+
+    #[test]
+    fn rejects_invalid_cert_expiry() {
+        assert!(matches!(parse_err(&["--check-cert-expiry", "x", URL]), ConfigError::InvalidCertExpiry(_)));
+    }
+
+    #[test]
+    fn rejects_invalid_confirm_thresholds() {
+        assert!(matches!(parse_err(&["--confirm-requests", "x", URL]), ConfigError::InvalidConfirmRequests(_)));
+        assert!(matches!(parse_err(&["--confirm-concurrency", "x", URL]), ConfigError::InvalidConfirmConcurrency(_)));
+    }
+
+    #[test]
+    fn rejects_invalid_warmup() {
+        assert_eq!(parse_err(&["--warmup", "0", URL]), ConfigError::InvalidWarmup("0".to_string()));
+    }
+
+    #[test]
+    fn rejects_invalid_think_time() {
+        assert_eq!(parse_err(&["--think-time", "500ms-100ms", URL]), ConfigError::InvalidThinkTime("500ms-100ms".to_string()));
+    }
+
+    #[test]
+    fn rejects_invalid_stages() {
+        assert!(matches!(parse_err(&["--stage", "60s", URL]), ConfigError::InvalidStage(_)));
+        assert!(matches!(parse_err(&["--stage", "0s:c=5", URL]), ConfigError::InvalidStage(_)));
+    }
+
+    #[test]
+    fn stage_conflicts_with_the_load_options() {
+        assert_eq!(parse_err(&["--stage", "10s:c=2", "-c", "3", URL]), ConfigError::StageConflict);
+    }
+
+    #[test]
+    fn rejects_invalid_adaptive_timeout() {
+        assert_eq!(parse_err(&["--adaptive-timeout", "0.5", URL]), ConfigError::InvalidAdaptiveTimeout("0.5".to_string()));
+    }
+
+    #[test]
+    fn rejects_invalid_hist_max() {
+        assert_eq!(parse_err(&["--hist-max", "-1", URL]), ConfigError::InvalidHistMax("-1".to_string()));
+    }
+
+    #[test]
+    fn rejects_invalid_sla() {
+        assert_eq!(parse_err(&["--sla", "0", URL]), ConfigError::InvalidSla("0".to_string()));
+        assert_eq!(parse_err(&["--sla", "fast", URL]), ConfigError::InvalidSla("fast".to_string()));
+    }
+
+    #[test]
+    fn rejects_invalid_percentiles() {
+        assert_eq!(parse_err(&["--percentiles", "50,101", URL]), ConfigError::InvalidPercentiles("101".to_string()));
+    }
+
+    #[test]
+    fn parses_fail_if() {
+        let config = parse(&["--fail-if", "p99>200ms", "--fail-if", "error_rate>1%", "--fail-if", "rps<100", URL]).unwrap();
+        let conditions: Vec<_> = config.fail_if
+            .iter()
+            .map(|condition| (condition.metric.clone(), condition.comparison.clone(), condition.threshold))
+            .collect();
+        assert_eq!(conditions, [
+            (FailMetric::Percentile(99.0), Comparison::Greater, 200.0),
+            (FailMetric::ErrorRate, Comparison::Greater, 1.0),
+            (FailMetric::RequestsPerSec, Comparison::Less, 100.0),
+        ]);
+        assert_eq!(config.fail_if[0].expression, "p99>200ms");
+    }
+
+    #[test]
+    fn rejects_invalid_fail_if() {
+        assert_eq!(parse_err(&["--fail-if", "p99", URL]), ConfigError::InvalidFailIf("p99".to_string()));
+    }
+
+    #[test]
+    fn rejects_invalid_http_version() {
+        assert_eq!(parse_err(&["--http-version", "3", URL]), ConfigError::InvalidHttpVersion("3".to_string()));
+    }
+
+    #[test]
+    fn rejects_invalid_latency_unit() {
+        assert_eq!(parse_err(&["--latency-unit", "ns", URL]), ConfigError::InvalidLatencyUnit("ns".to_string()));
+    }
+
+    #[test]
+    fn rejects_invalid_expectations() {
+        assert_eq!(parse_err(&["--expect-status", "200,99", URL]), ConfigError::InvalidExpectStatus("200,99".to_string()));
+        assert!(matches!(parse_err(&["--expect-substring", "", URL]), ConfigError::InvalidExpectSubstring(_)));
+    }
+
+    #[test]
+    fn output_files_need_a_path() {
+        assert!(matches!(parse_err(&[URL, "--record"]), ConfigError::InvalidRecord(_)));
+        assert!(matches!(parse_err(&[URL, "--out-file"]), ConfigError::InvalidOutFile(_)));
+        assert!(matches!(parse_err(&[URL, "--timeline"]), ConfigError::InvalidTimeline(_)));
+        assert!(matches!(parse_err(&[URL, "--raw-csv"]), ConfigError::InvalidRawCsv(_)));
+    }
+
+    #[test]
+    fn tokio_console_needs_the_feature() {
+        if !cfg!(feature = "tokio-console") {
+            assert_eq!(parse_err(&["--tokio-console", URL]), ConfigError::TokioConsoleUnavailable);
+        }
+    }
+
+    #[test]
+    fn rejects_a_missing_url() {
+        assert_eq!(parse_err(&["-n", "5"]), ConfigError::UrlNotProvided);
+    }
+
+    #[test]
+    fn rejects_malformed_urls() {
+        assert_eq!(parse_err(&["not a url"]), ConfigError::InvalidUrl("not a url".to_string()));
+        assert_eq!(parse_err(&["/relative/path"]), ConfigError::InvalidUrl("/relative/path".to_string()));
+        assert_eq!(parse_err(&["http:///path"]), ConfigError::InvalidUrl("http:///path".to_string()));
+        assert_eq!(parse_err(&["http:/host"]), ConfigError::InvalidUrl("http:/host".to_string()));
+    }
+
+    #[test]
+    fn rejects_unsupported_schemes() {
+        for url in ["ftp://example.com/file", "file:///etc/hosts", "mailto:someone@example.com", "ws://example.com"] {
+            assert_eq!(parse_err(&[url]), ConfigError::UnsupportedScheme(url.to_string()));
+        }
+        assert_eq!(
+            Config::builder("ftp://example.com").build().unwrap_err(),
+            ConfigError::UnsupportedScheme("ftp://example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_a_missing_urls_file() {
+        assert!(matches!(parse_err(&["--urls-file", "/nonexistent/urls.txt"]), ConfigError::InvalidUrlsFile(_)));
+    }
+
+    #[test]
+    fn rejects_invalid_auth() {
+        assert_eq!(parse_err(&["--basic-auth", "nopass", URL]), ConfigError::InvalidBasicAuth("nopass".to_string()));
+        assert!(matches!(parse_err(&["--bearer", "", URL]), ConfigError::InvalidBearer(_)));
+        assert_eq!(parse_err(&["--bearer", "a", "--basic-auth", "u:p", URL]), ConfigError::AuthTwice);
+    }
+
+    #[test]
+    fn rejects_invalid_bodies() {
+        assert_eq!(parse_err(&["--body", "a", "--body", "b", URL]), ConfigError::BodyTwice);
+        assert!(matches!(parse_err(&["--body-file", "/nonexistent/body", URL]), ConfigError::InvalidBodyFile(_)));
+        assert!(matches!(parse_err(&[URL, "--body"]), ConfigError::InvalidBody(_)));
+    }
+
+    #[test]
+    fn rejects_invalid_cookies() {
+        assert_eq!(parse_err(&["--cookie", "session", URL]), ConfigError::InvalidCookie("session".to_string()));
+        assert!(matches!(parse_err(&["--cookie", "a=b;c", URL]), ConfigError::InvalidCookie(_)));
+    }
+
+    #[test]
+    fn rejects_invalid_graphql() {
+        assert!(matches!(parse_err(&["--graphql", " ", URL]), ConfigError::InvalidGraphql(_)));
+        assert!(matches!(parse_err(&["--graphql-file", "/nonexistent/query", URL]), ConfigError::InvalidGraphqlFile(_)));
+        assert_eq!(parse_err(&["--graphql", "{ a }", "--graphql", "{ b }", URL]), ConfigError::GraphqlQueryTwice);
+        assert!(matches!(
+            parse_err(&["--graphql", "{ a }", "--graphql-variables", "[]", URL]),
+            ConfigError::InvalidGraphqlVariables(_)
+        ));
+        assert_eq!(parse_err(&["--graphql", "{ a }", "--body", "b", URL]), ConfigError::BodyWithGraphql);
+    }
+
+    #[test]
+    fn rejects_invalid_forms() {
+        assert_eq!(parse_err(&["--form", "a=1", "--body", "b", URL]), ConfigError::FormWithBody);
+        assert_eq!(parse_err(&["--form", "=1", URL]), ConfigError::InvalidForm("=1".to_string()));
+        assert!(matches!(parse_err(&["--form-file", "f=@/nonexistent/file", URL]), ConfigError::InvalidFormFile(_)));
+    }
+
+    #[test]
+    fn cookie_seeds_the_jar() {
+        let config = parse(&["--cookie", "session=abc", "--cookie=theme=dark", URL]).unwrap();
+        assert!(config.cookies);
+        assert_eq!(
+            config.seed_cookies,
+            vec![("session".to_string(), "abc".to_string()), ("theme".to_string(), "dark".to_string())]
+        );
+        assert!(!parse(&[URL]).unwrap().cookies);
+    }
+
+    #[test]
+    fn script_loads_the_requests() {
+        let dir = env::temp_dir().join(format!("benchener-script-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("order.json"), "{\"id\": 1}").unwrap();
+        let script = dir.join("mixed.txt");
+        std::fs::write(&script, "# mixed workload\nGET http://localhost/items\n\n3 post http://localhost/orders order.json\n").unwrap();
+        let config = parse(&["--script", script.to_str().unwrap()]);
+
+        std::fs::write(&script, "GET http://localhost/a extra.json extra\n").unwrap();
+        let too_many_fields = parse_err(&["--script", script.to_str().unwrap()]);
+        std::fs::write(&script, "0 GET http://localhost/a\n").unwrap();
+        let zero_weight = parse_err(&["--script", script.to_str().unwrap()]);
+        std::fs::write(&script, "# nothing\n").unwrap();
+        let empty = parse_err(&["--script", script.to_str().unwrap()]);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let config = config.unwrap();
+        assert_eq!(config.url, "http://localhost/items");
+        assert_eq!(config.script.len(), 2);
+        assert_eq!((config.script[0].method.clone(), config.script[0].weight), (Method::GET, 1));
+        assert!(config.script[0].body.is_none());
+        assert_eq!((config.script[1].method.clone(), config.script[1].weight), (Method::POST, 3));
+        assert_eq!(config.script[1].body.as_deref(), Some(b"{\"id\": 1}".as_slice()));
+
+        assert!(matches!(too_many_fields, ConfigError::InvalidScriptLine(_)));
+        assert!(matches!(zero_weight, ConfigError::InvalidScriptLine(_)));
+        assert!(matches!(empty, ConfigError::EmptyScript(_)));
+        assert!(matches!(parse_err(&["--script", "/nonexistent/script.txt"]), ConfigError::InvalidScript(_)));
+    }
+
+    #[test]
+    fn resolve_pins_hosts_to_addresses() {
+        let config = parse(&["--resolve", "Example.com:443:10.0.0.5", "--resolve=example.com:80:[::1]", URL]).unwrap();
+        assert_eq!(
+            config.resolve,
+            vec![
+                ("example.com".to_string(), 443, "10.0.0.5".parse().unwrap()),
+                ("example.com".to_string(), 80, "::1".parse().unwrap())
+            ]
+        );
+        for spec in ["example.com:443", ":443:10.0.0.5", "example.com:https:10.0.0.5", "example.com:443:backend"] {
+            assert_eq!(parse_err(&["--resolve", spec, URL]), ConfigError::InvalidResolve(spec.to_string()));
+        }
+    }
+
+    #[test]
+    fn ramp_follows_the_elapsed_time() {
+        let config = parse(&["--ramp", "0:1000:30s", "-d", "1m", URL]).unwrap();
+        assert_eq!(config.concurrency, 1000);
+        assert_eq!(config.concurrency_at(Duration::ZERO), 1);
+        assert_eq!(config.concurrency_at(Duration::from_secs(15)), 500);
+        assert_eq!(config.concurrency_at(Duration::from_secs(45)), 1000);
+
+        let down = parse(&["--ramp=100:10:10s", "-d", "20s", URL]).unwrap();
+        assert_eq!(down.concurrency, 100);
+        assert_eq!(down.concurrency_at(Duration::from_secs(5)), 55);
+        assert_eq!(parse(&["-c", "8", URL]).unwrap().concurrency_at(Duration::from_secs(5)), 8);
+    }
+
+    #[test]
+    fn rejects_invalid_ramps() {
+        for spec in ["0:10", "a:10:5s", "0:0:5s", "0:10:0s", "0:10:soon"] {
+            assert_eq!(parse_err(&["--ramp", spec, "-d", "20s", URL]), ConfigError::InvalidRamp(spec.to_string()));
+        }
+    }
+
+    #[test]
+    fn ramp_requires_a_duration_test() {
+        assert_eq!(parse_err(&["--ramp", "0:10:5s", URL]), ConfigError::RampConflict);
+        assert_eq!(parse_err(&["--ramp", "0:10:5s", "-d", "10s", "-c", "5", URL]), ConfigError::RampConflict);
+    }
+
+    #[test]
+    fn rejects_invalid_config_files() {
+        assert_eq!(
+            parse_err(&["--config", "/nonexistent/bench.toml", URL]),
+            ConfigError::InvalidConfigFile("/nonexistent/bench.toml".to_string())
+        );
+
+        let path = env::temp_dir().join(format!("benchener-config-test-{}.toml", std::process::id()));
+        std::fs::write(&path, "unknown_key = 1\n").unwrap();
+        let err = parse_err(&["--config", path.to_str().unwrap(), URL]);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(err, ConfigError::InvalidConfig { .. }));
+    }
+
+    #[test]
+    fn errors_keep_the_messages() {
+        assert_eq!(ConfigError::InvalidRequests(String::new()).to_string(), ERR_INVALID_REQUESTS);
+        assert_eq!(ConfigError::InvalidRequests("0".to_string()).to_string(), format!("\"0\"\n{}", ERR_INVALID_REQUESTS));
+        assert_eq!(ConfigError::BodyTwice.to_string(), ERR_BODY_TWICE);
+    }
+
+    #[test]
+    fn builder_validates_the_options() {
+        assert!(Config::builder(URL).requests(10).concurrency(2).build().is_ok());
+        assert_eq!(Config::builder("nope").build().unwrap_err(), ConfigError::InvalidUrl("nope".to_string()));
+        assert_eq!(
+            Config::builder(URL).requests(1).concurrency(2).build().unwrap_err(),
+            ConfigError::InvalidRequestsAndConcurrency
+        );
+    }
+
+    #[test]
+    fn parses_insecure() {
+        assert!(!parse(&[URL]).unwrap().insecure);
+        assert!(parse(&["-k", URL]).unwrap().insecure);
+        assert!(parse(&["--insecure", URL]).unwrap().insecure);
+    }
+
+    #[test]
+    fn builder_sets_insecure() {
+        assert!(!Config::builder(URL).build().unwrap().insecure);
+        assert!(Config::builder(URL).insecure(true).build().unwrap().insecure);
+    }
+
+    #[test]
+    fn urls_sample_picks_from_the_file() {
+        let path = env::temp_dir().join(format!("benchener-urls-test-{}.txt", std::process::id()));
+        let listed: Vec<String> = (0..20).map(|i| format!("http://localhost/{}", i)).collect();
+        std::fs::write(&path, listed.join("\n")).unwrap();
+        let file = path.to_str().unwrap();
+        let sampled = parse(&["--urls-file", file, "--urls-sample", "5", "--seed", "7"]).unwrap();
+        let replayed = parse(&["--urls-sample=5", "--seed", "7", "--urls-file", file]).unwrap();
+        let unseeded = parse(&["--urls-file", file, "--urls-sample", "5"]).unwrap();
+        let with_url = parse(&["--urls-file", file, "--urls-sample", "3", URL]).unwrap();
+        let everything = parse(&["--urls-file", file, "--urls-sample", "50"]).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(sampled.urls.len(), 5);
+        assert!(sampled.urls.is_sorted_by_key(|url| listed.iter().position(|listed| listed == url).unwrap()));
+        assert_eq!(sampled.urls, replayed.urls);
+        assert_eq!(sampled.sampled_urls(), Some(sampled.urls.as_slice()));
+        assert!(unseeded.seed.is_some()); // reported, so the sample can be replayed
+        assert_eq!(with_url.urls[0], URL);
+        assert_eq!(with_url.sampled_urls().unwrap().len(), 3);
+        assert_eq!((everything.urls, everything.urls_in_files), (listed, 20));
+    }
+
+    #[test]
+    fn rejects_invalid_urls_samples() {
+        assert_eq!(parse_err(&["--urls-sample", "0", URL]), ConfigError::InvalidUrlsSample("0".to_string()));
+        assert_eq!(parse_err(&["--urls-sample", "x", URL]), ConfigError::InvalidUrlsSample("x".to_string()));
+        assert_eq!(parse_err(&["--urls-sample", "3", URL]), ConfigError::UrlsSampleWithoutFile);
+    }
 }
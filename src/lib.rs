@@ -0,0 +1,17 @@
+//! Benchener as a library, for running benchmarks from a Rust harness instead of the command line
+//!
+//! ```no_run
+//! use benchener::{ Config, Runner };
+//!
+//! let config = Config::builder("http://localhost:8080").requests(1000).concurrency(10).build().unwrap();
+//! let report = Runner::new(config).run().unwrap();
+//! println!("{} completed, p99 {}ms", report.completed_requests, report.latency_percentile(99.0));
+//! ```
+
+pub mod config;
+pub mod runner;
+pub mod report;
+
+pub use config::{ Config, ConfigBuilder, ConfigError };
+pub use runner::{ Runner, RunError };
+pub use report::Report;
@@ -1,27 +1,156 @@
-mod config;
-mod runner;
-mod report;
+use benchener::{ Config, ConfigError, Runner, RunError, config::TestType, report::ReportSummary };
+use std::io::{ self, IsTerminal, Write };
 
-use config::Config;
-use runner::Runner;
+const EXIT_FAILED_CONDITION: i32 = 2; // a --fail-if condition held
+const EXIT_ABORTED: i32 = 3; // the test was stopped by --max-errors / --max-error-rate
 
 fn main() {
-    let config = Config::parse();
+    let config = match Config::parse() {
+        Ok(config) => config,
+        Err(ConfigError::Help) => {
+            Config::print_help();
+            std::process::exit(0);
+        }
+        Err(ConfigError::Version) => {
+            println!("{}", ConfigError::Version);
+            std::process::exit(0);
+        }
+        Err(ConfigError::UnknownArgument(_)) => {
+            Config::print_help();
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+
+    if let Some(path) = &config.verify {
+        match ReportSummary::verify_fingerprint(path) {
+            Ok(fingerprint) => println!("Fingerprint OK: {}", fingerprint),
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if !config.dry_run && !config.yes && config.is_high_impact() && io::stdout().is_terminal() && !confirm_run(&config) {
+        eprintln!("Aborted");
+        std::process::exit(1);
+    }
+
+    #[cfg(feature = "tokio-console")]
+    if config.tokio_console {
+        console_subscriber::init(); // serves on 127.0.0.1:6669
+    }
+
+    // every open connection needs a socket, make sure the process is allowed to open them
+    let fd_limit = raise_fd_limit(config.open_files_needed());
+    let dry_run = config.dry_run;
+    let mut runner = Runner::new(config);
+    if let Some(warning) = fd_limit.and_then(|fd_limit| runner.record_fd_limit(fd_limit)) {
+        eprintln!("Warning: {}", warning);
+    }
 
-    let runner = Runner::new(config);
+    if dry_run {
+        if let Err(err) = runner.dry_run() {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
 
     let runner_clone = runner.clone();
 
+    // the first Ctrl+C stops the test (the report is printed once run returns), the second one exits
     ctrlc
         ::set_handler(move || {
-            runner_clone.print_report();
-            std::process::exit(0);
+            if runner_clone.is_stopping() {
+                std::process::exit(130);
+            }
+            eprintln!("\nStopping, press Ctrl+C again to exit immediately");
+            runner_clone.stop();
         })
         .expect("Error setting Ctrl+C handler");
 
-    let result = runner.run();
-    match result {
-        Ok(res) => { res.print_report() }
-        Err(err) => { eprintln!("{}", err) }
+    if let Err(err) = runner.run() {
+        if !err.is_aborted() {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        eprintln!("\n{}", err); // ends the progress line
+        // what ran before the abort is still worth seeing (the warmup measures nothing)
+        if matches!(err, RunError::Aborted { .. }) {
+            if let Err(err) = runner.print_report() {
+                eprintln!("Failed to print report: {}", err);
+            }
+        }
+        std::process::exit(EXIT_ABORTED);
+    }
+    if let Err(err) = runner.print_report() {
+        eprintln!("Failed to print report: {}", err);
+        std::process::exit(1);
+    }
+    if let Err(err) = runner.check_strict() {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+    if let Err(err) = runner.check_fail_conditions() {
+        eprintln!("{}", err);
+        std::process::exit(EXIT_FAILED_CONDITION);
+    }
+}
+
+/// Raise the soft open file limit (towards the hard limit) to `needed`, returns the resulting soft limit
+#[cfg(unix)]
+#[allow(clippy::unnecessary_cast)] // rlim_t is not u64 on every unix
+fn raise_fd_limit(needed: u64) -> Option<u64> {
+    let mut limit = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+    if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } != 0 {
+        return None;
+    }
+
+    if (limit.rlim_cur as u64) < needed {
+        let raised = libc::rlimit {
+            rlim_cur: (needed as libc::rlim_t).min(limit.rlim_max),
+            rlim_max: limit.rlim_max,
+        };
+        if unsafe { libc::setrlimit(libc::RLIMIT_NOFILE, &raised) } == 0 {
+            limit = raised;
+        }
+    }
+    Some(limit.rlim_cur as u64)
+}
+
+#[cfg(not(unix))]
+fn raise_fd_limit(_needed: u64) -> Option<u64> {
+    None // no per-process socket limit to check
+}
+
+/// Ask the user before starting a run that could overload the target
+fn confirm_run(config: &Config) -> bool {
+    if config.test_type == TestType::Duration {
+        print!(
+            "This will send requests for {:?} at concurrency {} to {}. Continue? [y/N] ",
+            config.duration,
+            config.concurrency,
+            config.url
+        );
+    } else {
+        print!(
+            "This will send {} requests at concurrency {} to {}. Continue? [y/N] ",
+            config.requests,
+            config.concurrency,
+            config.url
+        );
+    }
+    io::stdout().flush().unwrap();
+
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
     }
+    matches!(answer.trim(), "y" | "Y" | "yes" | "Yes")
 }
@@ -1,21 +1,258 @@
-use std::time::Duration;
+use std::collections::{ BTreeMap, HashMap, VecDeque };
+use std::{ fmt, fs, io };
+use std::net::SocketAddr;
+use std::time::{ Duration, Instant, SystemTime, UNIX_EPOCH };
+use hdrhistogram::Histogram;
+use serde::Serialize;
 
-#[derive(Debug)]
+const ADAPTIVE_WINDOW: usize = 1000; // recent latencies the adaptive timeout is derived from
+const ADAPTIVE_CALIBRATION: usize = 100; // latencies observed before the adaptive timeout kicks in
+const ADAPTIVE_REFRESH: usize = 100; // recompute the adaptive timeout every these many latencies
+const LATENCY_SIGFIGS: u8 = 3; // significant figures kept by the latency histogram
+// fields of the json report the fingerprint covers (the ones a results database compares runs on)
+const FINGERPRINT_FIELDS: [&str; 10] = [
+    "completed_requests",
+    "failed_requests",
+    "timeouts",
+    "non_2xx_responses",
+    "status_counts",
+    "duration_secs",
+    "requests_per_sec",
+    "latency_samples",
+    "latency_ms",
+    "percentiles_ms",
+];
+const LATENCY_MAX_MICROS: u64 = 3_600_000_000; // highest trackable latency (1h), longer ones are clamped
+
+#[derive(Debug, Clone)]
 pub struct Report {
     pub server_software: String, // server software ( e.g nginx/1.18.0 (Ubuntu) )
     pub host: String, // hostname of the server
     pub port: u16, // port of the server
+    pub cert_expiry: Option<String>, // expiry date of the server certificate (with --check-cert-expiry)
 
     pub completed_requests: usize, // total valid request/response cycles
     pub failed_requests: usize, // total number of failed reqeusts
     pub timeouts: usize, // total timeouts
-    pub total_html_read: f64, // total html read in KB's
+    pub connection_resets: usize, // requests failed by the server resetting or closing the connection
+    pub retries_total: usize, // retried attempts (with --retries), a request is only failed once they ran out
+    pub dns_failures: HashMap<String, usize>, // failed name resolutions per hostname
+    pub errors_by_kind: BTreeMap<String, usize>, // failed requests per isahc error kind (e.g ConnectionFailed)
+    pub total_html_read: f64, // total html read in KB's (decompressed)
+    pub total_wire_body_read: f64, // total response bodies as sent over the wire (compressed) in KB's
+    pub total_header_bytes: f64, // total response headers (names + values) read in KB's
     pub non_2xx_responses: usize, // total non 2.x.x status code responses
-    pub concurrency: usize, // concurrency level
+    pub informational_1xx: usize, // 1.x.x responses (part of the non 2.x.x ones)
+    pub redirect_3xx: usize, // 3.x.x responses that were not followed (part of the non 2.x.x ones)
+    pub client_error_4xx: usize, // 4.x.x responses (part of the non 2.x.x ones)
+    pub server_error_5xx: usize, // 5.x.x responses (part of the non 2.x.x ones)
+    pub status_counts: BTreeMap<u16, usize>, // responses per status code (sorted by code)
+    pub protocol_counts: BTreeMap<String, usize>, // responses per negotiated HTTP version (e.g HTTP/2.0)
+    pub script_counts: BTreeMap<usize, usize>, // sent requests per --script line (by index in the script)
+    pub adaptive_timeout: Option<Duration>, // current adaptive deadline (None while calibrating)
+    pub adaptive_timeouts: usize, // requests killed by the adaptive deadline
+    pub recent_latencies: VecDeque<f64>, // sliding window (ms) for the adaptive timeout
+    pub graphql_errors: usize, // responses carrying a GraphQL "errors" field
+    pub validation_failures: usize, // responses failing --expect-status / --expect-substring (counted as failed)
+    pub concurrency: usize, // outstanding requests at a time
+    pub connections: Option<usize>, // max open connections (None when unbounded)
+    pub follow_redirects: bool, // 3xx responses were followed (so they don't count as non 2.x.x)
+    pub blocked_redirects: usize, // redirects not followed because of --restrict-redirects (counted as 3xx)
+    pub seed: u64, // seed of the rng behind the randomized behavior (replayed with --seed)
+    pub name: Option<String>, // label of the run (--name)
+
+    pub duration: Duration, // duration of the test so far (kept up to date by the timer threads)
+    pub started_at: Option<Instant>, // wall clock start of the measured test
+    pub started_utc: Option<SystemTime>, // absolute time the run began (for correlating with server logs)
+    pub finished_at: Option<Instant>, // wall clock end of the measured test (None while running)
+    pub requests_per_connection: HashMap<SocketAddr, usize>, // completed requests per connection (keyed by local address)
+    pub rps_samples: Vec<usize>, // requests completed during each second of the test
+    pub sampled_completed: usize, // completed requests at the time of the last throughput sample
+    pub timeline: Vec<TimelineSample>, // running totals at the end of each second of the test
+    pub fd_limit: Option<u64>, // soft limit of open file descriptors (unix only)
+    pub warnings: Vec<String>, // advisory warnings raised during the run (errors with --strict)
+    pub cut_short: bool, // stopped by --max-duration before every request finished
+
+    pub latencies: Histogram<u64>, // latency of each request in µs (memory stays bounded regardless of request count)
+    pub latency_total_ms: f64, // sum of the recorded latencies
+    pub live_latencies: Option<Histogram<u64>>, // latencies since the last --live refresh in µs (None without --live)
+    pub corrected_latencies: Histogram<u64>, // latency from the scheduled send time in µs (with --correct-co)
+    pub phases: PhaseTimings, // time spent in each phase of the requests
+    pub server_timings: BTreeMap<String, ServerTiming>, // backend durations per Server-Timing metric (e.g db)
+    pub connect_times: Histogram<u64>, // DNS + connect + TLS of each request in µs (0 on a reused connection)
+    pub ttfb_times: Histogram<u64>, // time to the first response byte of each request in µs
+    pub total_times: Histogram<u64>, // time until the whole body was read of each request in µs
+    pub queue_times: Histogram<u64>, // time each request waited inside the client before being sent in µs
+    pub continue_times: Histogram<u64>, // wait for the 100 Continue before the body was sent in µs (with --expect-continue)
+    pub upload_times: Histogram<u64>, // time spent sending the request body in µs (with --expect-continue)
+    pub latency_exemplars: BTreeMap<usize, Exemplar>, // latest request per latency bucket, by bucket index (with --request-id-header)
+}
+
+/// A request standing for the latency bucket it fell in (an OpenMetrics exemplar)
+#[derive(Debug, Clone)]
+pub struct Exemplar {
+    pub request_id: String, // as sent in the --request-id-header
+    pub latency: Duration,
+    pub at: SystemTime, // when the request completed
+}
 
-    pub duration: Duration, // total duration of the test
+/// Accumulated time (ms) spent in each phase of the requests
+#[derive(Debug, Default, Clone)]
+pub struct PhaseTimings {
+    pub dns: f64, // name resolution
+    pub connect: f64, // tcp handshake
+    pub tls: f64, // tls handshake
+    pub server: f64, // waiting for the first byte of the response
+    pub transfer: f64, // sending the request and reading the response
+    pub samples: usize, // requests the phases were accumulated over
+}
 
-    pub latencies: Vec<f64>, // latency of each request in ms (will be used for showing latency distribution)
+/// Durations a Server-Timing metric reported (with --server-timing)
+#[derive(Debug, Default, Clone)]
+pub struct ServerTiming {
+    pub total_ms: f64, // sum of the reported durations
+    pub samples: usize, // responses reporting the metric
+}
+
+/// Running totals at a second boundary of the test (with --timeline)
+#[derive(Debug, Clone)]
+pub struct TimelineSample {
+    pub completed: usize, // completed requests so far
+    pub failed: usize, // failed requests so far
+    pub latency_total_ms: f64, // sum of the latencies recorded so far
+}
+
+/// Machine readable snapshot of a report (with --output json)
+#[derive(Debug, Serialize)]
+pub struct ReportSummary {
+    pub name: Option<String>, // label of the run (--name), null when not given
+    pub started_at: Option<String>, // UTC start of the run (RFC 3339)
+    pub url: String, // tested url
+    pub sampled_urls: Option<Vec<String>>, // urls picked from the urls file by --urls-sample, null without it
+    pub host: String, // hostname of the server
+    pub port: u16, // port of the server
+    pub server_software: String, // server software ( e.g nginx/1.18.0 (Ubuntu) )
+    pub concurrency: usize, // outstanding requests at a time
+    pub connections: Option<usize>, // max open connections (None when unbounded)
+
+    pub completed_requests: usize, // total valid request/response cycles
+    pub failed_requests: usize, // total number of failed requests
+    pub timeouts: usize, // total timeouts
+    pub connection_resets: usize, // requests failed by the server resetting or closing the connection
+    pub retries_total: usize, // retried attempts (with --retries)
+    pub errors_by_kind: BTreeMap<String, usize>, // failed requests per error kind
+    pub non_2xx_responses: usize, // total non 2.x.x status code responses
+    pub informational_1xx: usize, // 1.x.x responses
+    pub redirect_3xx: usize, // 3.x.x responses that were not followed
+    pub client_error_4xx: usize, // 4.x.x responses
+    pub server_error_5xx: usize, // 5.x.x responses
+    pub status_counts: BTreeMap<u16, usize>, // responses per status code
+    pub protocol_counts: BTreeMap<String, usize>, // responses per negotiated HTTP version
+    pub redirects_followed: bool, // whether 3xx responses were followed (not counted as non 2.x.x)
+    pub blocked_redirects: usize, // redirects --restrict-redirects did not follow (part of the 3xx ones)
+    pub graphql_errors: usize, // responses carrying a GraphQL "errors" field
+    pub validation_failures: usize, // responses failing --expect-status / --expect-substring
+    pub seed: u64, // pass to --seed to replay the random choices
+
+    pub duration_secs: f64, // total duration of the test
+    pub requests_per_sec: f64, // completed requests per second
+    pub total_html_read_kb: f64, // total html read in KB's (decompressed)
+    pub total_wire_body_read_kb: f64, // response bodies as sent over the wire in KB's
+    pub transfer_per_sec_kb: f64, // html read per second in KB's
+    pub total_transferred_kb: f64, // wire bodies and response headers read in KB's
+    pub total_transfer_per_sec_kb: f64, // html and response headers read per second in KB's
+
+    pub latency_ms: Option<LatencyStats>, // None when no request completed
+    pub percentiles_ms: BTreeMap<String, f64>, // keyed like "p99" / "p99.9" (--percentiles)
+    pub reliable_percentiles: bool, // enough samples (--min-samples) for the percentiles
+    pub latency_samples: usize, // latencies the stats are computed from
+    pub corrected_percentiles_ms: BTreeMap<String, f64>, // from the scheduled send time (empty without --correct-co)
+    pub connect_ms: Option<LatencyStats>, // DNS + connect + TLS (None without request metrics)
+    pub ttfb_ms: Option<LatencyStats>, // time to first byte
+    pub total_ms: Option<LatencyStats>, // time until the body was read
+    pub continue_ms: Option<LatencyStats>, // wait for the 100 Continue (None without --expect-continue)
+    pub upload_ms: Option<LatencyStats>, // time spent sending the request body (None without --expect-continue)
+    pub stages: Vec<StageSummary>, // per stage breakdown (empty without --stage)
+    pub warnings: Vec<String>, // advisory warnings raised during the run
+    pub cut_short: bool, // stopped by --max-duration before every request finished
+    pub fingerprint: String, // sha256 of the core metrics, checked by --verify
+}
+
+/// Results of a single --stage
+#[derive(Debug, Serialize)]
+pub struct StageSummary {
+    pub stage: String, // as given (e.g 60s:c=10)
+    pub concurrency: usize,
+    pub duration_secs: f64,
+    pub completed_requests: usize,
+    pub failed_requests: usize,
+    pub requests_per_sec: f64,
+    pub latency_ms: Option<LatencyStats>, // None when no request of the stage completed
+    pub percentiles_ms: BTreeMap<String, f64>, // keyed like "p99" (--percentiles)
+}
+
+/// Latency stats in ms
+#[derive(Debug, Serialize)]
+pub struct LatencyStats {
+    pub min: f64,
+    pub avg: f64,
+    pub max: f64,
+    pub stdev: f64,
+}
+
+/// Why a --verify failed
+#[derive(Debug)]
+pub enum VerifyError {
+    Read(String, io::Error), // path of the report
+    InvalidJson(String, serde_json::Error),
+    MissingFingerprint(String),
+    Mismatch {
+        expected: String, // stored in the report
+        actual: String, // recomputed from its metrics
+    },
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerifyError::Read(path, err) => write!(f, "Failed to read {}: {}", path, err),
+            VerifyError::InvalidJson(path, err) => write!(f, "{} is not a json report: {}", path, err),
+            VerifyError::MissingFingerprint(path) => write!(f, "{} has no fingerprint", path),
+            VerifyError::Mismatch { expected, actual } =>
+                write!(f, "Fingerprint mismatch, the report says {} but its metrics hash to {}", expected, actual),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+impl ReportSummary {
+    /// Sha256 (hex) of the core metrics of a json report, serialized with sorted keys
+    /// so the same metrics always hash the same (floats round-trip exactly with float_roundtrip)
+    pub fn fingerprint(summary: &serde_json::Value) -> String {
+        let core: BTreeMap<&str, &serde_json::Value> = FINGERPRINT_FIELDS
+            .iter()
+            .map(|&field| (field, summary.get(field).unwrap_or(&serde_json::Value::Null)))
+            .collect();
+        let canonical = serde_json::to_string(&core).unwrap_or_default();
+        openssl::sha::sha256(canonical.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// Recompute the fingerprint of the json report at path, returns it when it matches the stored one
+    pub fn verify_fingerprint(path: &str) -> Result<String, VerifyError> {
+        let contents = fs::read_to_string(path).map_err(|err| VerifyError::Read(path.to_string(), err))?;
+        let summary: serde_json::Value = serde_json
+            ::from_str(&contents)
+            .map_err(|err| VerifyError::InvalidJson(path.to_string(), err))?;
+        let Some(expected) = summary.get("fingerprint").and_then(|value| value.as_str()) else {
+            return Err(VerifyError::MissingFingerprint(path.to_string()));
+        };
+        let actual = Self::fingerprint(&summary);
+        if actual != expected {
+            return Err(VerifyError::Mismatch { expected: expected.to_string(), actual });
+        }
+        Ok(actual)
+    }
 }
 
 impl Default for Report {
@@ -24,15 +261,363 @@ impl Default for Report {
             server_software: "".to_string(),
             host: "".to_string(),
             port: 0,
+            cert_expiry: None,
             completed_requests: 0,
             failed_requests: 0,
             timeouts: 0,
+            connection_resets: 0,
+            retries_total: 0,
+            dns_failures: HashMap::new(),
+            errors_by_kind: BTreeMap::new(),
             total_html_read: 0.0,
+            total_wire_body_read: 0.0,
+            total_header_bytes: 0.0,
             non_2xx_responses: 0,
+            informational_1xx: 0,
+            redirect_3xx: 0,
+            client_error_4xx: 0,
+            server_error_5xx: 0,
+            status_counts: BTreeMap::new(),
+            protocol_counts: BTreeMap::new(),
+            script_counts: BTreeMap::new(),
+            adaptive_timeout: None,
+            adaptive_timeouts: 0,
+            recent_latencies: VecDeque::new(),
+            graphql_errors: 0,
+            validation_failures: 0,
             concurrency: 0,
+            connections: None,
+            follow_redirects: false,
+            blocked_redirects: 0,
+            seed: 0,
+            name: None,
 
             duration: Duration::from_secs(0),
-            latencies: Vec::new(),
+            started_at: None,
+            started_utc: None,
+            finished_at: None,
+            requests_per_connection: HashMap::new(),
+            rps_samples: Vec::new(),
+            sampled_completed: 0,
+            timeline: Vec::new(),
+            fd_limit: None,
+            warnings: Vec::new(),
+            cut_short: false,
+            latencies: Self::new_histogram(),
+            latency_total_ms: 0.0,
+            live_latencies: None,
+            corrected_latencies: Self::new_histogram(),
+            phases: PhaseTimings::default(),
+            server_timings: BTreeMap::new(),
+            connect_times: Self::new_histogram(),
+            ttfb_times: Self::new_histogram(),
+            total_times: Self::new_histogram(),
+            queue_times: Self::new_histogram(),
+            continue_times: Self::new_histogram(),
+            upload_times: Self::new_histogram(),
+            latency_exemplars: BTreeMap::new(),
+        }
+    }
+}
+
+impl Report {
+    /// Record the latency of a request
+    pub fn record_latency(&mut self, latency: Duration) {
+        self.latencies.saturating_record(latency.as_micros() as u64);
+        if let Some(live_latencies) = self.live_latencies.as_mut() {
+            live_latencies.saturating_record(latency.as_micros() as u64);
+        }
+        self.latency_total_ms += latency.as_secs_f64() * 1000.0;
+    }
+
+    /// Record the latency of a request measured from its scheduled send time
+    pub fn record_corrected_latency(&mut self, latency: Duration) {
+        self.corrected_latencies.saturating_record(latency.as_micros() as u64);
+    }
+
+    /// Record the connect, time to first byte and total times of a request (from its metrics)
+    pub fn record_request_times(&mut self, connect: Duration, ttfb: Duration, total: Duration) {
+        self.connect_times.saturating_record(connect.as_micros() as u64);
+        self.ttfb_times.saturating_record(ttfb.as_micros() as u64);
+        self.total_times.saturating_record(total.as_micros() as u64);
+    }
+
+    /// Record the wait for the 100 Continue and the body upload time of a request
+    pub fn record_continue_times(&mut self, wait: Duration, upload: Duration) {
+        self.continue_times.saturating_record(wait.as_micros() as u64);
+        self.upload_times.saturating_record(upload.as_micros() as u64);
+    }
+
+    /// Number of recorded latencies
+    pub fn latency_count(&self) -> usize {
+        self.latencies.len() as usize
+    }
+
+    /// Latency (ms) at percentile `p`
+    pub fn latency_percentile(&self, p: f64) -> f64 {
+        Self::histogram_percentile(&self.latencies, p)
+    }
+
+    /// Min, avg, max and stdev of the latencies (None when nothing was recorded)
+    pub fn latency_stats(&self) -> Option<LatencyStats> {
+        Self::histogram_stats(&self.latencies)
+    }
+
+    /// Min, avg, max and stdev (ms) of a histogram in µs (None when nothing was recorded)
+    pub fn histogram_stats(histogram: &Histogram<u64>) -> Option<LatencyStats> {
+        if histogram.is_empty() {
+            return None;
+        }
+        Some(LatencyStats {
+            min: Self::micros_to_ms(histogram.min()),
+            avg: histogram.mean() / 1000.0,
+            max: Self::micros_to_ms(histogram.max()),
+            stdev: histogram.stdev() / 1000.0,
+        })
+    }
+
+    /// Value (ms) at percentile `p` of a histogram in µs
+    pub fn histogram_percentile(histogram: &Histogram<u64>, p: f64) -> f64 {
+        Self::micros_to_ms(histogram.value_at_quantile(p / 100.0))
+    }
+
+    /// Distinct recorded latencies (ms, at the histogram's precision) with their counts, ascending
+    pub fn latency_counts(&self) -> Vec<(f64, u64)> {
+        self.latencies
+            .iter_recorded()
+            .map(|value| (Self::micros_to_ms(value.value_iterated_to()), value.count_at_value()))
+            .collect()
+    }
+
+    /// Empty latency histogram (µs) with the precision and range of the report's ones
+    pub fn new_histogram() -> Histogram<u64> {
+        Histogram::new_with_max(LATENCY_MAX_MICROS, LATENCY_SIGFIGS).unwrap()
+    }
+
+    fn micros_to_ms(micros: u64) -> f64 {
+        (micros as f64) / 1000.0
+    }
+
+    /// Record the requests completed (and the timeline totals) for each second of the test elapsed so far
+    pub fn sample_throughput(&mut self) {
+        while (self.rps_samples.len() as u64) < self.duration.as_secs() {
+            self.rps_samples.push(self.completed_requests - self.sampled_completed);
+            self.sampled_completed = self.completed_requests;
+            self.timeline.push(TimelineSample {
+                completed: self.completed_requests,
+                failed: self.failed_requests,
+                latency_total_ms: self.latency_total_ms,
+            });
+        }
+    }
+
+    /// Take the measurements out of the report, leaving it ready for another pass
+    /// (details about the target like host or server software are kept)
+    pub fn take_measurements(&mut self) -> Report {
+        let fresh = Report {
+            server_software: self.server_software.clone(),
+            host: self.host.clone(),
+            port: self.port,
+            cert_expiry: self.cert_expiry.clone(),
+            concurrency: self.concurrency,
+            connections: self.connections,
+            follow_redirects: self.follow_redirects,
+            seed: self.seed,
+            name: self.name.clone(),
+            started_utc: self.started_utc,
+            fd_limit: self.fd_limit,
+            warnings: self.warnings.clone(),
+            live_latencies: self.live_latencies.as_ref().map(|_| Self::new_histogram()),
+            ..Default::default()
+        };
+        std::mem::replace(self, fresh)
+    }
+
+    /// Add the measurements of a later pass (e.g the next --stage) to this report
+    pub fn merge(&mut self, other: &Report) {
+        // the timeline keeps running totals, continue them from where this report ended
+        let (completed, failed, latency_total_ms) = (
+            self.completed_requests,
+            self.failed_requests,
+            self.latency_total_ms,
+        );
+        self.timeline.extend(
+            other.timeline.iter().map(|sample| TimelineSample {
+                completed: completed + sample.completed,
+                failed: failed + sample.failed,
+                latency_total_ms: latency_total_ms + sample.latency_total_ms,
+            })
+        );
+
+        self.completed_requests += other.completed_requests;
+        self.failed_requests += other.failed_requests;
+        self.timeouts += other.timeouts;
+        self.connection_resets += other.connection_resets;
+        self.retries_total += other.retries_total;
+        for (host, count) in &other.dns_failures {
+            *self.dns_failures.entry(host.clone()).or_insert(0) += count;
+        }
+        for (kind, count) in &other.errors_by_kind {
+            *self.errors_by_kind.entry(kind.clone()).or_insert(0) += count;
+        }
+        self.total_html_read += other.total_html_read;
+        self.total_wire_body_read += other.total_wire_body_read;
+        self.total_header_bytes += other.total_header_bytes;
+        self.non_2xx_responses += other.non_2xx_responses;
+        self.informational_1xx += other.informational_1xx;
+        self.redirect_3xx += other.redirect_3xx;
+        self.blocked_redirects += other.blocked_redirects;
+        self.client_error_4xx += other.client_error_4xx;
+        self.server_error_5xx += other.server_error_5xx;
+        for (status, count) in &other.status_counts {
+            *self.status_counts.entry(*status).or_insert(0) += count;
+        }
+        for (protocol, count) in &other.protocol_counts {
+            *self.protocol_counts.entry(protocol.clone()).or_insert(0) += count;
+        }
+        for (index, count) in &other.script_counts {
+            *self.script_counts.entry(*index).or_insert(0) += count;
+        }
+        self.adaptive_timeouts += other.adaptive_timeouts;
+        self.graphql_errors += other.graphql_errors;
+        self.validation_failures += other.validation_failures;
+        self.concurrency = self.concurrency.max(other.concurrency);
+        self.server_software = other.server_software.clone();
+        self.warnings = other.warnings.clone(); // carried over from pass to pass
+        self.cut_short |= other.cut_short;
+
+        self.duration += other.duration;
+        self.started_at = self.started_at.or(other.started_at);
+        self.started_utc = self.started_utc.or(other.started_utc);
+        self.finished_at = other.finished_at.or(self.finished_at);
+        for (connection, count) in &other.requests_per_connection {
+            *self.requests_per_connection.entry(*connection).or_insert(0) += count;
+        }
+        self.rps_samples.extend(&other.rps_samples);
+
+        // the histograms share the same bounds, adding can't fail
+        let _ = self.latencies.add(&other.latencies);
+        self.latency_total_ms += other.latency_total_ms;
+        let _ = self.corrected_latencies.add(&other.corrected_latencies);
+        self.phases.dns += other.phases.dns;
+        self.phases.connect += other.phases.connect;
+        self.phases.tls += other.phases.tls;
+        self.phases.server += other.phases.server;
+        self.phases.transfer += other.phases.transfer;
+        self.phases.samples += other.phases.samples;
+        for (name, timing) in &other.server_timings {
+            let merged = self.server_timings.entry(name.clone()).or_default();
+            merged.total_ms += timing.total_ms;
+            merged.samples += timing.samples;
+        }
+        let _ = self.connect_times.add(&other.connect_times);
+        let _ = self.ttfb_times.add(&other.ttfb_times);
+        let _ = self.total_times.add(&other.total_times);
+        let _ = self.queue_times.add(&other.queue_times);
+        let _ = self.continue_times.add(&other.continue_times);
+        let _ = self.upload_times.add(&other.upload_times);
+        for (bucket, exemplar) in &other.latency_exemplars {
+            self.latency_exemplars.insert(*bucket, exemplar.clone()); // the other report ran later
+        }
+    }
+
+    /// Track a latency (ms) in the sliding window and periodically set the adaptive
+    /// deadline to `multiplier` x the window's p99, never above the static timeout
+    pub fn update_adaptive_timeout(&mut self, latency: f64, multiplier: f64, max: Duration) {
+        if self.recent_latencies.len() == ADAPTIVE_WINDOW {
+            self.recent_latencies.pop_front();
+        }
+        self.recent_latencies.push_back(latency);
+
+        let seen = self.latency_count();
+        if seen < ADAPTIVE_CALIBRATION || !seen.is_multiple_of(ADAPTIVE_REFRESH) {
+            return;
+        }
+        let mut window: Vec<f64> = self.recent_latencies.iter().copied().collect();
+        window.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let p99 = Self::percentile(&window, 99.0);
+
+        let deadline = Duration::from_secs_f64((p99 * multiplier) / 1000.0);
+        self.adaptive_timeout = Some(deadline.min(max));
+    }
+
+    pub fn mark_started(&mut self) {
+        self.started_at = Some(Instant::now());
+    }
+
+    pub fn mark_finished(&mut self) {
+        self.finished_at = Some(Instant::now());
+    }
+
+    /// UTC start of the run as RFC 3339 (e.g 2025-01-31T09:05:00Z)
+    pub fn started_rfc3339(&self) -> Option<String> {
+        self.started_utc.map(Self::format_rfc3339)
+    }
+
+    /// Get the value at percentile `p` from sorted values (nearest-rank: the smallest value
+    /// with at least p% of the values at or below it)
+    pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+        let rank = ((p * (sorted.len() as f64)) / 100.0).ceil() as usize; // multiply first to avoid 7.000001 ranks
+        sorted[rank.clamp(1, sorted.len()) - 1]
+    }
+
+    /// Format a point in time as RFC 3339 in UTC, to the second
+    pub fn format_rfc3339(time: SystemTime) -> String {
+        let secs = time.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs());
+        let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+
+        // civil date from the days since 1970-01-01 (Howard Hinnant's algorithm, in 400 year eras)
+        let z = days + 719_468;
+        let era = z / 146_097;
+        let day_of_era = z - era * 146_097;
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153; // month counted from March
+        let day = day_of_year - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            year,
+            month,
+            day,
+            secs_of_day / 3600,
+            (secs_of_day % 3600) / 60,
+            secs_of_day % 60
+        )
+    }
+
+    /// Wall clock time the test took (so far, while running), all throughput math is based on it
+    pub fn elapsed(&self) -> Duration {
+        match (self.started_at, self.finished_at) {
+            (Some(started), Some(finished)) => finished - started,
+            (Some(started), None) => started.elapsed(),
+            _ => self.duration,
+        }
+    }
+
+    /// `value` per second of the test (0 when no time elapsed)
+    pub fn per_sec(&self, value: f64) -> f64 {
+        let elapsed = self.elapsed().as_secs_f64();
+        if elapsed > 0.0 { value / elapsed } else { 0.0 }
+    }
+
+    /// Body (as sent over the wire) and header KB's read
+    pub fn total_transferred(&self) -> f64 {
+        self.total_wire_body_read + self.total_header_bytes
+    }
+
+    /// Whether any response body was compressed on the wire
+    pub fn has_compressed_bodies(&self) -> bool {
+        self.total_wire_body_read != self.total_html_read
+    }
+
+    /// Percentage of the completed requests that returned GraphQL errors
+    pub fn graphql_error_rate(&self) -> f64 {
+        if self.completed_requests == 0 {
+            return 0.0;
         }
+        ((self.graphql_errors as f64) / (self.completed_requests as f64)) * 100.0
     }
 }
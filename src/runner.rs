@@ -1,23 +1,120 @@
-use crate::config::{ Config, TestType };
-use crate::report::Report;
-
-use std::sync::{ Arc, Mutex, atomic::{ AtomicBool, Ordering } };
-use std::net::TcpStream;
-use std::io::Write;
+use crate::config::{ Config, TestType, OutputFormat, Warmup, FailMetric, LatencyUnit, HttpVersion, Stage, RequestSpec };
+use crate::report::{ Report, ReportSummary, StageSummary, TimelineSample, Exemplar };
+
+use std::collections::BTreeMap;
+use std::sync::{ Arc, Mutex, atomic::{ AtomicBool, AtomicUsize, Ordering } };
+use std::net::{ IpAddr, SocketAddr, TcpStream, ToSocketAddrs };
+use std::io::{ self, Write, BufWriter };
+use std::fs::File;
+use std::fmt;
+use std::pin::Pin;
+use std::task::{ Context, Poll };
+use std::time::{ SystemTime, UNIX_EPOCH };
 use tokio::time::Instant;
-use url::Url;
+use hdrhistogram::Histogram;
+use url::{ Host, Url };
+use rand::{ Rng, SeedableRng, rngs::StdRng };
+use openssl::{ asn1::Asn1Time, ssl::{ SslConnector, SslMethod, SslVerifyMode } };
 use isahc::{
     HttpClient,
     HttpClientBuilder,
-    config::Configurable,
+    config::{ Configurable, ResolveMap, SslOption, VersionNegotiation },
+    cookies::{ Cookie, CookieJar },
+    http::{ Method, Uri },
     error::ErrorKind,
     AsyncReadResponseExt,
+    ResponseExt,
+    Request,
+    Response,
+    AsyncBody,
+    Metrics,
+};
+use tokio::{
+    sync::Notify,
+    runtime::{ Builder, Runtime },
+    task::JoinSet,
+    time::{ interval, sleep, sleep_until, Duration },
 };
-use tokio::{ sync::Notify, runtime::{ Builder, Runtime }, time::{ sleep, Duration } };
 // use ctrlc;
 
 const FIELD_WIDTH: usize = 24; //  width of each field for formatting print
-const BUCKET_COUNT: usize = 10; // size of the histogram
+const HISTOGRAM_BAR_WIDTH: usize = 40; // width of the bar of the fullest histogram bucket
+const MIN_THROUGHPUT_SAMPLES: usize = 5; // seconds of samples needed for throughput percentiles
+const BREAKDOWN_WIDTH: usize = 60; // width of the stacked bar in the phase breakdown
+const QUEUE_SATURATION_MS: f64 = 10.0; // p99 client queue time above which the pool is considered saturated
+const PROGRESS_WIDTH: usize = 30; // width of the progress bar
+const LIVE_STATS_WIDTH: usize = 44; // width of the --live stats after the progress detail
+const PROMETHEUS_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]; // latency buckets (s)
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100); // progress refresh of a request count test
+const RAMP_POLL_INTERVAL: Duration = Duration::from_millis(10); // how often an idle pool worker checks the --ramp
+const ERROR_RATE_CALIBRATION: usize = 100; // finished requests before --max-error-rate is enforced
+const REDIRECT_LIMIT: usize = 50; // redirects followed per request without --max-redirects (curl's default)
+
+type RequestBody<'a> = Option<(&'a [u8], Option<&'a str>)>; // body and its content type
+
+/// Why a run failed, the report of what ran so far stays in the runner
+#[derive(Debug)]
+pub enum RunError {
+    Unreachable(String, String), // url, reason
+    CreateFile(String, io::Error), // path of a --record / --out-file / --raw-csv file
+    AbortedDuringWarmup,
+    Aborted {
+        failed: usize,
+        completed: usize,
+    },
+}
+
+impl RunError {
+    /// Whether the run was stopped by --max-errors / --max-error-rate
+    pub fn is_aborted(&self) -> bool {
+        matches!(self, RunError::AbortedDuringWarmup | RunError::Aborted { .. })
+    }
+}
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RunError::Unreachable(url, reason) => write!(f, "Failed to reach {}: {}", url, reason),
+            RunError::CreateFile(path, err) => write!(f, "Failed to create {}: {}", path, err),
+            RunError::AbortedDuringWarmup => write!(f, "Aborted during the warmup, too many requests failed"),
+            RunError::Aborted { failed, completed } =>
+                write!(
+                    f,
+                    "Aborted: {} request(s) failed ({:.1}%), {} completed before the abort",
+                    failed,
+                    ((*failed as f64) / ((failed + completed) as f64)) * 100.0,
+                    completed
+                ),
+        }
+    }
+}
+
+impl std::error::Error for RunError {}
+
+/// When the client first and last read the request body (with --expect-continue)
+#[derive(Debug, Default)]
+struct BodyReads {
+    first: Option<Instant>, // curl starts reading the body once the 100 Continue arrived
+    last: Option<Instant>,
+}
+
+/// Request body that records when it is read, to tell the 100 Continue wait from the upload
+struct TimedBody {
+    body: futures_lite::io::Cursor<Vec<u8>>,
+    reads: Arc<Mutex<BodyReads>>,
+}
+
+impl futures_lite::io::AsyncRead for TimedBody {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        let now = Instant::now();
+        {
+            let mut reads = self.reads.lock().unwrap();
+            reads.first.get_or_insert(now);
+            reads.last = Some(now);
+        }
+        Pin::new(&mut self.body).poll_read(cx, buf)
+    }
+}
 
 /// Runner structure with configuration and a shared report.
 #[derive(Debug, Clone)]
@@ -25,57 +122,416 @@ pub struct Runner {
     config: Config,
     report: Arc<Mutex<Report>>, // final report
     client: HttpClient, // client for sending requests
+    recorder: Arc<Mutex<Option<BufWriter<File>>>>, // log of sent requests (with --record), opened in run
+    raw_csv: Arc<Mutex<Option<BufWriter<File>>>>, // row per completed request (with --raw-csv), opened after the warmup
+    raw_rows: Arc<AtomicUsize>, // rows written to the raw csv (the index of the next one)
+    cold_report: Option<Report>, // report of the first pass with --cache-comparison
+    stage_reports: Vec<(Stage, Report)>, // report of each --stage (the final report adds them up)
+    next_slot: Arc<Mutex<Option<Instant>>>, // next dispatch time with --rate
+    next_url: Arc<AtomicUsize>, // round-robin position in config.urls
+    stopping: Arc<AtomicBool>, // set on Ctrl+C, the batch loops finish early
+    stop_notify: Arc<Notify>, // wakes a batch loop waiting on in-flight requests on Ctrl+C
+    aborted: Arc<AtomicBool>, // set when --max-errors / --max-error-rate stopped the test
+    rng: Arc<Mutex<StdRng>>, // shared rng for randomized behavior (e.g think time)
+    live_stats: Arc<Mutex<String>>, // last second's stats appended to the progress line (with --live)
+    run_id: String, // seed of the run in hex, prefixes the --request-id-header ids
+    request_ids: Arc<AtomicUsize>, // requests numbered so far (with --request-id-header)
 }
 
 impl Runner {
     /*------------------==| Public Functions |==-------------------------*/
     /// Create a new Runner instance
     pub fn new(config: Config) -> Self {
-        let client = HttpClientBuilder::new()
+        let mut client_builder = HttpClientBuilder::new()
             .timeout(config.timeout)
             .connect_timeout(config.connection_timeout)
-            .build()
-            .unwrap();
-
-        let mut report = Report::default();
-        report.concurrency = config.concurrency; // set the concurrency in report
+            .metrics(true) // needed for separating in-client queue time from network latency
+            // outstanding requests beyond the pool size wait inside the client for a free connection
+            .max_connections(config.connections.unwrap_or(0)) // 0 means no limit
+            .max_connections_per_host(config.connections.unwrap_or(0));
+        if config.no_keepalive {
+            // finished connections are closed instead of cached for the next request
+            client_builder = client_builder.connection_cache_size(0);
+        }
+        if config.no_decompress {
+            client_builder = client_builder.automatic_decompression(false);
+        }
+        if let Some(version) = &config.http_version {
+            client_builder = client_builder.version_negotiation(match version {
+                HttpVersion::Http11 => VersionNegotiation::http11(),
+                HttpVersion::Http2 => VersionNegotiation::http2(),
+            });
+        }
+        if !config.resolve.is_empty() {
+            // curl connects to the pinned address, the Host header and TLS server name stay the hostname
+            let resolve = config.resolve
+                .iter()
+                .fold(ResolveMap::new(), |map, (host, port, address)| map.add(host, *port, *address));
+            client_builder = client_builder.dns_resolve(resolve);
+        }
+        if config.insecure {
+            // self signed / expired certificates and mismatched hostnames are accepted
+            client_builder = client_builder.ssl_options(
+                SslOption::DANGER_ACCEPT_INVALID_CERTS | SslOption::DANGER_ACCEPT_INVALID_HOSTS
+            );
+        }
+        if config.cookies {
+            // one jar shared by every request, cookies without a domain are seeded for each host
+            let cookie_jar = CookieJar::new();
+            for (name, value) in &config.seed_cookies {
+                for url in &config.urls {
+                    if let (Ok(uri), Ok(cookie)) = (url.parse::<Uri>(), Cookie::builder(name, value).build()) {
+                        let _ = cookie_jar.set(cookie, &uri);
+                    }
+                }
+            }
+            client_builder = client_builder.cookie_jar(cookie_jar);
+        }
+        let client = client_builder.build().unwrap();
+
+        // printed and reported, so a run can be replayed with --seed
+        let seed = config.seed.unwrap_or_else(rand::random);
+
+        let report = Report {
+            concurrency: config.concurrency, // set the concurrency in report
+            connections: config.connections,
+            seed,
+            name: config.name.clone(),
+            follow_redirects: config.follow_redirects,
+            live_latencies: config.live.then(Report::new_histogram),
+            ..Default::default()
+        };
 
         Self {
             config,
             report: Arc::new(Mutex::new(report)),
             client,
+            recorder: Arc::new(Mutex::new(None)),
+            raw_csv: Arc::new(Mutex::new(None)),
+            raw_rows: Arc::new(AtomicUsize::new(0)),
+            cold_report: None,
+            stage_reports: Vec::new(),
+            next_slot: Arc::new(Mutex::new(None)),
+            next_url: Arc::new(AtomicUsize::new(0)),
+            stopping: Arc::new(AtomicBool::new(false)),
+            stop_notify: Arc::new(Notify::new()),
+            aborted: Arc::new(AtomicBool::new(false)),
+            rng: Arc::new(Mutex::new(StdRng::seed_from_u64(seed))),
+            live_stats: Arc::new(Mutex::new(String::new())),
+            run_id: format!("{:016x}", seed),
+            request_ids: Arc::new(AtomicUsize::new(0)),
         }
     }
 
-    /// Main entry point to run the benchmarking tool
-    pub fn run(self) -> Result<Self, String> {
-        // check if the url is reachable
-        if let Err(_) = self.is_url_reachable(&self.config.url) {
-            return Err(format!("Failed to resolve {}", self.config.url));
+    /// Main entry point to run the benchmarking tool, returns the final report
+    /// (on an error the runner keeps the report of what ran, e.g for printing it after an abort)
+    pub fn run(&mut self) -> Result<Report, RunError> {
+        // the passes take the runner by value, it's put back with their results
+        let (runner, result) = self.clone().run_passes();
+        *self = runner;
+        result.map(|()| self.report())
+    }
+
+    /// Record the open file limit of the process (raised by the caller for the sockets of the test),
+    /// returns a warning when it's still too low for the connections
+    pub fn record_fd_limit(&self, fd_limit: u64) -> Option<String> {
+        let mut report = self.report.lock().unwrap();
+        report.fd_limit = Some(fd_limit);
+        if fd_limit >= self.config.open_files_needed() {
+            return None;
         }
-        if self.config.test_type == TestType::RequestCount {
-            Ok(self.run_req_count_test())
-        } else if self.config.test_type == TestType::Duration {
-            Ok(self.run_duration_test())
-        } else {
-            Ok(self.run_both_tests())
+        let warning = format!(
+            "open file limit ({}) is too low for {} connections, raise it with `ulimit -n`",
+            fd_limit,
+            self.config.sockets()
+        );
+        report.warnings.push(warning.clone());
+        Some(warning)
+    }
+
+    /// Check that every url is reachable and print what the test would do, without sending any request
+    pub fn dry_run(&self) -> Result<(), String> {
+        for url in self.targets() {
+            if let Err(e) = self.is_url_reachable(url) {
+                return Err(format!("Failed to reach {}: {}", url, e));
+            }
         }
+        self.print_plan(&mut io::stdout().lock()).map_err(|e| format!("Failed to print plan: {}", e))
     }
 
-    /// Print the benchmarking report
-    pub fn print_report(&self) {
-        if self.config.summarize {
-            self.print_summarized_report();
+    /// Copy of the report (the final one once run returned)
+    pub fn report(&self) -> Report {
+        self.report.lock().unwrap().clone()
+    }
+
+    /// Stop the test early (on Ctrl+C), the in-flight requests are abandoned and run returns
+    pub fn stop(&self) {
+        self.stopping.store(true, Ordering::Relaxed);
+        self.stop_notify.notify_one(); // keeps a permit if no loop is waiting right now
+    }
+
+    /// Whether the test was stopped early
+    pub fn is_stopping(&self) -> bool {
+        self.stopping.load(Ordering::Relaxed)
+    }
+
+    /// Whether the test was stopped for exceeding --max-errors / --max-error-rate
+    pub fn is_aborted(&self) -> bool {
+        self.aborted.load(Ordering::Relaxed)
+    }
+
+    /// Fails with the collected warnings when running with --strict
+    pub fn check_strict(&self) -> Result<(), String> {
+        let warnings = self.warnings();
+        if !self.config.strict || warnings.is_empty() {
+            return Ok(());
+        }
+        let mut message = format!("Strict mode: {} warning(s) raised", warnings.len());
+        for warning in warnings {
+            message.push_str(&format!("\n - {}", warning));
+        }
+        Err(message)
+    }
+
+    /// Write out the recording and timeline, fails if the test was aborted on errors
+    fn finish(self) -> (Self, Result<(), RunError>) {
+        self.flush_recording();
+        self.write_timeline();
+        if self.is_aborted() {
+            let report = self.report.lock().unwrap();
+            let err = RunError::Aborted { failed: report.failed_requests, completed: report.completed_requests };
+            drop(report);
+            return (self, Err(err));
+        }
+        (self, Ok(()))
+    }
+
+    /// Fails with the --fail-if conditions that hold for the final report
+    pub fn check_fail_conditions(&self) -> Result<(), String> {
+        let report = self.report.lock().unwrap();
+        let mut failed = Vec::new();
+        for condition in &self.config.fail_if {
+            let latency = |value: f64| if report.latency_count() > 0 { Some(value) } else { None };
+            let value = match condition.metric {
+                FailMetric::Percentile(p) => latency(report.latency_percentile(p)),
+                FailMetric::AvgLatency => report.latency_stats().map(|stats| stats.avg),
+                FailMetric::MaxLatency => report.latency_stats().map(|stats| stats.max),
+                FailMetric::ErrorRate => {
+                    let finished = report.completed_requests + report.failed_requests;
+                    Some(if finished > 0 {
+                        ((report.failed_requests as f64) / (finished as f64)) * 100.0
+                    } else {
+                        0.0
+                    })
+                }
+                FailMetric::RequestsPerSec => Some(report.per_sec(report.completed_requests as f64)),
+                FailMetric::Errors => Some(report.failed_requests as f64),
+                FailMetric::Non2xx => Some(report.non_2xx_responses as f64),
+            };
+            // a latency condition can't pass without a single successful request
+            match value {
+                Some(value) if condition.holds(value) => {
+                    failed.push(format!("{} (was {:.2})", condition.expression, value));
+                }
+                None => failed.push(format!("{} (no successful requests)", condition.expression)),
+                _ => {}
+            }
+        }
+        if failed.is_empty() {
+            return Ok(());
+        }
+        let mut message = format!("Failed: {} condition(s) held", failed.len());
+        for condition in failed {
+            message.push_str(&format!("\n - {}", condition));
+        }
+        Err(message)
+    }
+
+    /// Flush the recorded requests and raw csv rows (if any) to disk, a failure is reported as a warning
+    fn flush_recording(&self) {
+        let mut failures = Vec::new();
+        if let Some(writer) = self.recorder.lock().unwrap().as_mut() {
+            if let Err(err) = writer.flush() {
+                failures.push(format!("failed to write the recording: {}", err));
+            }
+        }
+        if let Some(writer) = self.raw_csv.lock().unwrap().as_mut() {
+            if let Err(err) = writer.flush() {
+                failures.push(format!("failed to write the raw csv: {}", err));
+            }
+        }
+        self.report.lock().unwrap().warnings.extend(failures);
+    }
+
+    /// Write the per-second timeline (with --timeline) as `second,requests,avg_latency_ms,errors` rows
+    /// (after the name with --name), a failure is reported as a warning
+    fn write_timeline(&self) {
+        let Some(path) = &self.config.timeline else {
+            return;
+        };
+        let mut report = self.report.lock().unwrap();
+        let result = File::create(path).and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            let name_column = if self.config.name.is_some() { "name," } else { "" };
+            writeln!(writer, "{}second,requests,avg_latency_ms,errors", name_column)?;
+            let name = self.csv_name();
+
+            let mut previous = TimelineSample { completed: 0, failed: 0, latency_total_ms: 0.0 };
+            for (second, sample) in report.timeline.iter().enumerate() {
+                let requests = sample.completed - previous.completed;
+                let avg_latency = if requests > 0 {
+                    (sample.latency_total_ms - previous.latency_total_ms) / (requests as f64)
+                } else {
+                    0.0
+                };
+                let errors = sample.failed - previous.failed;
+                writeln!(writer, "{}{},{},{:.2},{}", name, second + 1, requests, avg_latency, errors)?;
+                previous = sample.clone();
+            }
+            writer.flush()
+        });
+        if let Err(err) = result {
+            report.warnings.push(format!("failed to write the timeline to {}: {}", path, err));
+        }
+    }
+
+    /// Print the benchmarking report (into --out-file if given)
+    pub fn print_report(&self) -> io::Result<()> {
+        if let Some(path) = &self.config.out_file {
+            let file = File::create(path).map_err(|e| {
+                io::Error::new(e.kind(), format!("Failed to create {}: {}", path, e))
+            })?;
+            let mut out = BufWriter::new(file);
+            self.write_report(&mut out)?;
+            out.flush()
         } else {
-            self.print_full_report();
+            self.write_report(&mut io::stdout().lock())
         }
     }
 
     /*-------------------==| Private/Helpers |==----------------------- */
 
+    /// Run the warmup and the measured passes, the runner comes back with the results even when it failed
+    fn run_passes(self) -> (Self, Result<(), RunError>) {
+        // check that every target is reachable, a typo in a urls file fails here rather than mid-run
+        for url in self.targets() {
+            if let Err(e) = self.is_url_reachable(url) {
+                let err = RunError::Unreachable(url.to_string(), e.to_string());
+                return (self, Err(err));
+            }
+        }
+        self.print_banner();
+        self.report.lock().unwrap().started_utc = Some(SystemTime::now()); // warmup included, it hits the server too
+        let create = |path: &String| File::create(path).map_err(|e| RunError::CreateFile(path.clone(), e));
+        if let Some(path) = &self.config.record {
+            match create(path) {
+                Ok(file) => *self.recorder.lock().unwrap() = Some(BufWriter::new(file)),
+                Err(err) => return (self, Err(err)),
+            }
+        }
+        if let Some(path) = &self.config.out_file {
+            // fail now rather than after a long test, the report overwrites it at the end
+            if let Err(err) = create(path) {
+                return (self, Err(err));
+            }
+        }
+        let raw_csv = match self.config.raw_csv.as_ref().map(create).transpose() {
+            Ok(raw_csv) => raw_csv,
+            Err(err) => return (self, Err(err)),
+        };
+
+        let runner = self.run_warmup();
+        if runner.is_aborted() {
+            runner.flush_recording();
+            return (runner, Err(RunError::AbortedDuringWarmup));
+        }
+        // rows are streamed as the requests complete, so memory doesn't grow with the request count
+        if let Some(file) = raw_csv {
+            let mut writer = BufWriter::new(file);
+            let name_column = if runner.config.name.is_some() { "name," } else { "" };
+            let _ = writeln!(writer, "{}index,latency_ms,status,bytes", name_column);
+            *runner.raw_csv.lock().unwrap() = Some(writer);
+        }
+        let runner = if !runner.config.stages.is_empty() {
+            runner.run_stages()
+        } else if runner.config.cache_comparison {
+            // first pass fills the caches, the second one measures them warm
+            let _ = writeln!(runner.status_out(), "Cold pass:");
+            let mut runner = runner.run_test();
+            if runner.is_stopping() {
+                return runner.finish(); // stopped during the cold pass, report it as is
+            }
+            let cold_report = runner.report.lock().unwrap().take_measurements();
+            runner.cold_report = Some(cold_report);
+            let _ = writeln!(runner.status_out(), "\nWarm pass:");
+            runner.run_test()
+        } else {
+            runner.run_test()
+        };
+        runner.finish()
+    }
+
+
+    /// Run the --stage load profile in order, each stage is measured on its own and added to the final report
+    fn run_stages(mut self) -> Self {
+        let stages = self.config.stages.clone();
+        let (duration, concurrency) = (self.config.duration, self.config.concurrency);
+        let mut total: Option<Report> = None;
+
+        for (i, stage) in stages.iter().enumerate() {
+            let _ = writeln!(
+                self.status_out(),
+                "{}Stage {}/{}: {:?} at concurrency {}",
+                if i > 0 { "\n" } else { "" },
+                i + 1,
+                stages.len(),
+                stage.duration,
+                stage.concurrency
+            );
+            self.config.duration = stage.duration;
+            self.config.concurrency = stage.concurrency;
+            self.report.lock().unwrap().concurrency = stage.concurrency;
+
+            self = self.run_test();
+            let stage_report = self.report.lock().unwrap().take_measurements();
+            match total.as_mut() {
+                Some(total) => total.merge(&stage_report),
+                None => {
+                    total = Some(stage_report.clone());
+                }
+            }
+            self.stage_reports.push((stage.clone(), stage_report));
+            if self.is_stopping() {
+                break; // the remaining stages are skipped, the finished ones are reported
+            }
+        }
+
+        self.config.duration = duration;
+        self.config.concurrency = concurrency;
+        if let Some(total) = total {
+            *self.report.lock().unwrap() = total;
+        }
+        self
+    }
+
+    /// Run the configured test once
+    fn run_test(self) -> Self {
+        *self.next_slot.lock().unwrap() = None; // a fresh schedule, a late warmup must not make the test look late
+        self.report.lock().unwrap().mark_started();
+        let runner = if self.config.test_type == TestType::RequestCount {
+            self.run_req_count_test()
+        } else if self.config.test_type == TestType::Duration {
+            self.run_duration_test()
+        } else {
+            self.run_both_tests()
+        };
+        runner.report.lock().unwrap().mark_finished();
+        runner
+    }
+
     /// Run the RequestCount test
     fn run_req_count_test(self) -> Self {
-        let runtime = Self::get_arc_runtime(&self.config.threads);
+        let runtime = Self::get_arc_runtime(&self.config);
 
         // To share runner across different async tasks
         let runner = Arc::new(self);
@@ -88,34 +544,68 @@ impl Runner {
 
         // new tokio async runtime
         runtime.block_on(async {
-            // Run total batches
-            let total_batches = runner.config.requests / runner.config.concurrency;
-            for batch in 1..=total_batches {
-                let _ = Self::run_batch(runner.clone(), runner.config.concurrency).await;
-                print!("\rCompleted requests: {}", batch * runner.config.concurrency); // move to the start of line and print
-                std::io::stdout().flush().unwrap(); // ensure the output is displayed immediately
-            }
-
-            // Run remainder
-            let remainder = runner.config.requests % runner.config.concurrency;
-            if remainder > 0 {
-                let _ = Self::run_batch(runner.clone(), remainder).await;
-                print!("\rCompleted requests: {}", runner.config.requests); // move to the start of line and print
-                std::io::stdout().flush().unwrap(); // ensure the output is displayed immediately
-            }
+            Self::run_request_pool(runner.clone(), runner.config.requests, true).await;
             stop_flag.store(true, Ordering::Relaxed);
             sleep(Duration::from_millis(10)).await; // wait for the timer_thread to stop
         });
 
-        Arc::try_unwrap(runner).unwrap_or_else(|_|
-            panic!("Runner instance still has active references.")
-        )
+        // drop the runtime to release the requests abandoned on Ctrl+C
+        drop(runtime);
+
+        Self::unwrap_runner(runner)
+    }
+
+    /// Keep `concurrency` requests in flight until `requests` were sent, a new one goes out as
+    /// soon as any finishes (a slow response holds up only its own slot, not a whole batch),
+    /// prints the requests progress with `show_progress`
+    async fn run_request_pool(runner: Arc<Runner>, requests: usize, show_progress: bool) {
+        let start = Instant::now();
+        let dispatched = Arc::new(AtomicUsize::new(0));
+        let mut workers = JoinSet::new();
+        for worker in 0..runner.config.concurrency.min(requests) {
+            let runner = runner.clone();
+            let dispatched = dispatched.clone();
+            workers.spawn(async move {
+                while !runner.is_stopping() {
+                    // with a --ramp the workers beyond the current concurrency wait their turn
+                    if worker >= runner.config.concurrency_at(start.elapsed()) {
+                        sleep(RAMP_POLL_INTERVAL).await;
+                        continue;
+                    }
+                    if dispatched.fetch_add(1, Ordering::Relaxed) >= requests {
+                        break;
+                    }
+                    runner.think().await;
+                    let _ = runner.send_request(&runner.client).await; // failures are counted in the report
+                }
+            });
+        }
+
+        let mut progress = interval(PROGRESS_INTERVAL);
+        loop {
+            tokio::select! {
+                worker = workers.join_next() => {
+                    if worker.is_none() { break; } // every request was sent
+                }
+                _ = progress.tick(), if show_progress => {
+                    let finished = {
+                        let report = runner.report.lock().unwrap();
+                        report.completed_requests + report.failed_requests
+                    };
+                    runner.print_request_progress(finished, start.elapsed());
+                }
+                _ = runner.stop_notify.notified() => { return; } // in-flight requests are abandoned with the workers
+            }
+        }
+        if show_progress && !runner.is_stopping() {
+            runner.print_request_progress(requests, start.elapsed());
+        }
     }
 
     /// Run duration test
     fn run_duration_test(self) -> Self {
         // new tokio async runtime
-        let runtime = Self::get_arc_runtime(&self.config.threads);
+        let runtime = Self::get_arc_runtime(&self.config);
 
         // To share runner across different async tasks
         let runner = Arc::new(self);
@@ -127,10 +617,14 @@ impl Runner {
         Self::spawn_duration_thread(Arc::clone(&runner), notify.clone());
 
         runtime.block_on(async {
-            // Infinite loop to keep sending requests till time ends
+            let start = Instant::now();
+
+            // Infinite loop to keep sending requests till time ends (each batch sized by the --ramp, if any)
             loop {
                 tokio::select! {
-                    _ = Self::run_batch(runner.clone(), runner.config.concurrency)=>{}
+                    running = Self::run_stoppable_batch(runner.clone(), runner.config.concurrency_at(start.elapsed())) => {
+                        if !running { break; } // stopped with Ctrl+C
+                    }
                     _ = notify.notified() => { break; } // break the loop on notify signal
                 }
             }
@@ -139,15 +633,13 @@ impl Runner {
         // drop the runtime to release any references to runner
         drop(runtime);
 
-        Arc::try_unwrap(runner).unwrap_or_else(|_|
-            panic!("Runner instance still has active references.")
-        )
+        Self::unwrap_runner(runner)
     }
 
     /// Run both tests whichever one finishes first will stop the test
     fn run_both_tests(self) -> Self {
         // new tokio async runtime
-        let runtime = Self::get_arc_runtime(&self.config.threads);
+        let runtime = Self::get_arc_runtime(&self.config);
 
         // To share runner across different async tasks
         let runner = Arc::new(self);
@@ -166,22 +658,12 @@ impl Runner {
         );
 
         runtime.block_on(async {
-            // Run total batches
-            let total_batches = runner.config.requests / runner.config.concurrency;
-            for _ in 0..total_batches {
-                tokio::select! {
-                     _ = Self::run_batch(runner.clone(), runner.config.concurrency) =>{}
-                     _ = notify.notified() => { break; }
-                }
-            }
-
-            // Run remainder
-            let remainder = runner.config.requests % runner.config.concurrency;
-            if remainder > 0 {
-                tokio::select! {
-                     _ = Self::run_batch(runner.clone(), remainder) => {}
-                     _ = notify.notified() => { return; }
-                }
+            // keep sending until the requests are sent or the duration elapses, whichever comes first
+            // (the duration thread prints the progress, the sleep covers a notify fired before the select waits)
+            tokio::select! {
+                _ = Self::run_request_pool(runner.clone(), runner.config.requests, false) => {}
+                _ = notify.notified() => {}
+                _ = sleep(runner.config.duration) => {}
             }
             stop_flag.store(true, Ordering::Relaxed);
             sleep(Duration::from_millis(10)).await; // wait for the duration_thread to stop
@@ -190,9 +672,51 @@ impl Runner {
         // drop the runtime to release runner references (if any)
         drop(runtime);
 
-        Arc::try_unwrap(runner).unwrap_or_else(|_|
-            panic!("Runner instance still has active references.")
-        )
+        Self::unwrap_runner(runner)
+    }
+
+    /// Send requests before the measured test (with --warmup), their results are discarded
+    fn run_warmup(self) -> Self {
+        let Some(warmup) = self.config.warmup.clone() else {
+            return self;
+        };
+        let _ = writeln!(self.status_out(), "Warming up..");
+
+        let runtime = Self::get_arc_runtime(&self.config);
+        let runner = Arc::new(self);
+
+        runtime.block_on(async {
+            match warmup {
+                Warmup::Requests(count) => {
+                    let mut remaining = count;
+                    while remaining > 0 {
+                        let batch = remaining.min(runner.config.concurrency);
+                        if !Self::run_stoppable_batch(runner.clone(), batch).await {
+                            break;
+                        }
+                        remaining -= batch;
+                    }
+                }
+                Warmup::Duration(duration) => {
+                    let _ = tokio::time::timeout(duration, async {
+                        while Self::run_stoppable_batch(runner.clone(), runner.config.concurrency).await {}
+                    }).await;
+                }
+            }
+        });
+
+        // drop the runtime to release the in-flight requests cut off by the warmup duration
+        drop(runtime);
+
+        let runner = Self::unwrap_runner(runner);
+        runner.report.lock().unwrap().take_measurements(); // the connections stay warm in the client pool
+        runner
+    }
+
+    /// Take the runner back out of the Arc, if a task or thread still holds a reference
+    /// fall back to a clone (which shares the same report) instead of losing the results
+    fn unwrap_runner(runner: Arc<Runner>) -> Runner {
+        Arc::try_unwrap(runner).unwrap_or_else(|runner| (*runner).clone())
     }
 
     /// Helper function for running batches
@@ -203,7 +727,12 @@ impl Runner {
         let mut handles = Vec::new();
         for _ in 0..count {
             let runner = runner.clone();
-            handles.push(tokio::spawn(async move { runner.send_request(&runner.client).await }));
+            handles.push(
+                tokio::spawn(async move {
+                    runner.think().await;
+                    runner.send_request(&runner.client).await
+                })
+            );
         }
         for handle in handles {
             handle.await??;
@@ -211,27 +740,264 @@ impl Runner {
         Ok(())
     }
 
+    /// Run a batch unless the test is stopped first, returns whether the test is still running
+    async fn run_stoppable_batch(runner: Arc<Runner>, count: usize) -> bool {
+        if runner.is_stopping() {
+            return false;
+        }
+        tokio::select! {
+            _ = Self::run_batch(runner.clone(), count) => !runner.is_stopping(),
+            _ = runner.stop_notify.notified() => false,
+        }
+    }
+
     /// Send the request
     async fn send_request(&self, client: &HttpClient) -> Result<(), isahc::Error> {
-        let start = Instant::now();
+        // a --script request brings its own method, url and body
+        let spec = self.next_spec();
+        let (method, url) = match spec {
+            Some((index, spec)) => {
+                *self.report.lock().unwrap().script_counts.entry(index).or_insert(0) += 1;
+                (spec.method.clone(), spec.url.as_str())
+            }
+            None => (self.config.request_method(), self.next_url()),
+        };
+        let spec = spec.map(|(_, spec)| spec);
+
+        let scheduled = self.wait_for_rate_slot().await;
 
-        let response = client.get_async(self.config.url.clone()).await;
+        // deadline derived from the observed latencies (None until calibrated)
+        let adaptive_timeout = if self.config.adaptive_timeout.is_some() {
+            self.report.lock().unwrap().adaptive_timeout
+        } else {
+            None
+        };
+
+        let request_body = match spec.and_then(|spec| spec.body.as_deref()) {
+            Some(body) => Some((body, None)),
+            None => self.config.request_body(),
+        };
+        if self.config.record.is_some() {
+            self.record_request(method.as_str(), url, request_body);
+        }
+
+        // the same id across --retries and redirects, it names the request not the attempt
+        let request_id = self.config.request_id_header
+            .as_ref()
+            .map(|_| format!("{}-{}", self.run_id, self.request_ids.fetch_add(1, Ordering::Relaxed)));
+        let body_reads = Arc::new(Mutex::new(BodyReads::default()));
+        let build_request = |method: &Method, url: &str, request_body: RequestBody<'_>, same_origin: bool| {
+            let mut builder = Request::builder().method(method.clone()).uri(url);
+            for (name, value) in &self.config.headers {
+                // like curl, credentials are not handed to another host a redirect points to
+                if !same_origin && (name.eq_ignore_ascii_case("authorization") || name.eq_ignore_ascii_case("cookie")) {
+                    continue;
+                }
+                builder = builder.header(name, value);
+            }
+            if let (Some(name), Some(id)) = (&self.config.request_id_header, &request_id) {
+                builder = builder.header(name, id);
+            }
+            if let Some(timeout) = adaptive_timeout {
+                builder = builder.timeout(timeout); // overrides the client wide timeout
+            }
+            let to_body = |body: &[u8]| {
+                if self.config.expect_continue {
+                    *body_reads.lock().unwrap() = BodyReads::default(); // of this attempt only
+                    let timed = TimedBody {
+                        body: futures_lite::io::Cursor::new(body.to_vec()),
+                        reads: body_reads.clone(),
+                    };
+                    AsyncBody::from_reader_sized(timed, body.len() as u64)
+                } else {
+                    AsyncBody::from(body.to_vec())
+                }
+            };
+            if self.config.expect_continue && request_body.is_some() {
+                builder = builder.header("expect", "100-continue");
+            }
+            match request_body {
+                Some((body, Some(content_type))) => builder.header("content-type", content_type).body(to_body(body)),
+                Some((body, None)) => builder.body(to_body(body)),
+                None => builder.body(AsyncBody::empty()),
+            }
+        };
 
-        let latency = start.elapsed();
+        // with --retries a transient failure is sent again, the latency is the one of the last attempt
+        let mut attempt = 0;
+        let response = loop {
+            let start = Instant::now();
+            let response = match self.send_following_redirects(client, &method, url, request_body, &build_request).await {
+                Ok((mut res, redirect_hops)) => {
+                    let latency = start.elapsed();
+                    match self.read_body(&mut res).await {
+                        Ok((body, html_read)) => Ok((res, redirect_hops, body, html_read, start, latency)),
+                        Err(err) => Err(isahc::Error::from(err)), // e.g the server closed the connection halfway through the body
+                    }
+                }
+                Err(err) => Err(err),
+            };
+            let retry = match &response {
+                Ok((res, ..)) => self.config.retry_on_5xx && res.status().is_server_error(),
+                Err(err) => Self::is_transient(err),
+            };
+            if !retry || attempt >= self.config.retries || self.is_stopping() {
+                break response;
+            }
+            attempt += 1;
+            self.report.lock().unwrap().retries_total += 1;
+            let backoff = self.config.retry_backoff.saturating_mul(2u32.saturating_pow(attempt as u32 - 1));
+            tokio::time::sleep(backoff).await;
+        };
 
         match response {
-            Ok(mut res) => {
-                let html_read = res.text().await?.len();
+            Ok((res, redirect_hops, body, html_read, start, latency)) => {
+                // each hop of a followed redirect has its own curl timings, the hops before
+                // the final response are part of its time to first byte
+                let redirected: Duration = redirect_hops.iter().map(Metrics::total_time).sum();
+
+                // time spent waiting inside the client before the transfer actually started
+                // (e.g waiting for a free connection), curl only starts its clock once it picks the request up
+                let queue_time = res
+                    .metrics()
+                    .map(|metrics| latency.saturating_sub(redirected + metrics.transfer_start_time()));
+
+                // curl counts the body bytes as received, before decoding any Content-Encoding
+                let wire_body_read = res
+                    .metrics()
+                    .map(|metrics| metrics.download_progress().0 as usize)
+                    .or_else(|| {
+                        res.headers()
+                            .get("content-length")
+                            .and_then(|length| length.to_str().ok()?.parse().ok())
+                    })
+                    .unwrap_or(html_read);
                 let mut report = self.report.lock().unwrap();
 
+                // an unexpected status or body (e.g an error page served with 200) fails the request
+                if !self.is_expected_response(res.status().as_u16(), &body) {
+                    report.failed_requests += 1;
+                    report.validation_failures += 1;
+                    *report.errors_by_kind.entry("ValidationFailed".to_string()).or_insert(0) += 1;
+                    if self.exceeds_error_limits(&report) && !self.aborted.swap(true, Ordering::Relaxed) {
+                        self.stop();
+                    }
+                    return Ok(());
+                }
+
+                // a GraphQL response with a top-level "errors" field failed even if the status is 200
+                if self.config.graphql_body.is_some() && Self::has_graphql_errors(&body) {
+                    report.graphql_errors += 1;
+                }
+
+                // the body is consumed, so the metrics are final
+                if let Some(metrics) = res.metrics() {
+                    let to_ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+                    let setup = |hop: &Metrics| hop.name_lookup_time() + hop.connect_time() + hop.secure_connect_time();
+                    let hops = || redirect_hops.iter().chain([metrics]);
+
+                    let phases = &mut report.phases;
+                    for hop in hops() {
+                        let dns = to_ms(hop.name_lookup_time());
+                        let connect = to_ms(hop.connect_time());
+                        let tls = to_ms(hop.secure_connect_time());
+                        phases.dns += dns;
+                        phases.connect += connect;
+                        phases.tls += tls;
+                        phases.server += (to_ms(hop.transfer_start_time()) - dns - connect - tls).max(0.0);
+                        phases.transfer += to_ms(hop.transfer_time());
+                    }
+                    phases.samples += 1;
+
+                    report.record_request_times(
+                        hops().map(setup).sum(),
+                        redirected + metrics.transfer_start_time(),
+                        redirected + metrics.total_time()
+                    );
+
+                    // the body is only read once the server answered the Expect: 100-continue
+                    // (or curl gave up waiting for it), the connection setup is not part of the wait
+                    let reads = body_reads.lock().unwrap();
+                    if let (Some(first), Some(last)) = (reads.first, reads.last) {
+                        report.record_continue_times(
+                            first.saturating_duration_since(start).saturating_sub(redirected + setup(metrics)),
+                            last.saturating_duration_since(first)
+                        );
+                    }
+                }
+
+                if self.config.server_timing {
+                    let metrics = res
+                        .headers()
+                        .get_all("server-timing")
+                        .iter()
+                        .filter_map(|value| value.to_str().ok())
+                        .flat_map(Self::parse_server_timing);
+                    for (name, duration) in metrics {
+                        let timing = report.server_timings.entry(name).or_default();
+                        timing.total_ms += duration;
+                        timing.samples += 1;
+                    }
+                }
+
+                // the local socket address identifies the connection the request was sent over
+                if let Some(local_addr) = res.local_addr() {
+                    *report.requests_per_connection.entry(local_addr).or_insert(0) += 1;
+                }
+
+                if let Some(queue_time) = queue_time {
+                    report.queue_times.saturating_record(queue_time.as_micros() as u64);
+                }
+
                 report.total_html_read += (html_read as f64) / 1024.0; // in KB's
-                report.latencies.push(latency.as_millis() as f64); // push latency for current request
+                report.total_wire_body_read += (wire_body_read as f64) / 1024.0; // in KB's
+                let header_bytes: usize = res
+                    .headers()
+                    .iter()
+                    .map(|(name, value)| name.as_str().len() + value.len())
+                    .sum();
+                report.total_header_bytes += (header_bytes as f64) / 1024.0; // in KB's
+                report.record_latency(latency); // record latency for current request
+                if let Some(request_id) = &request_id {
+                    // the latest request of each latency bucket is its exemplar in the OpenMetrics output
+                    let bucket = PROMETHEUS_BUCKETS
+                        .iter()
+                        .position(|&le| latency.as_secs_f64() <= le)
+                        .unwrap_or(PROMETHEUS_BUCKETS.len());
+                    let exemplar = Exemplar { request_id: request_id.clone(), latency, at: SystemTime::now() };
+                    report.latency_exemplars.insert(bucket, exemplar);
+                }
+                if let (true, Some(scheduled)) = (self.config.correct_co, scheduled) {
+                    // a request held up by slow responses still counts from when it should have gone out
+                    report.record_corrected_latency(latency + start.saturating_duration_since(scheduled));
+                }
+
+                if let Some(multiplier) = self.config.adaptive_timeout {
+                    report.update_adaptive_timeout(
+                        latency.as_secs_f64() * 1000.0,
+                        multiplier,
+                        self.config.timeout
+                    );
+                }
                 report.completed_requests += 1; // increment completed requests
 
-                // non 2.x.x responses
-                if res.status().as_u16() / 100 != 2 {
+                // non 2.x.x responses, counted per class as well
+                let status = res.status().as_u16();
+                if self.config.raw_csv.is_some() {
+                    self.write_raw_row(latency, status, html_read);
+                }
+                if !(200..300).contains(&status) {
                     report.non_2xx_responses += 1;
                 }
+                match status {
+                    100..=199 => report.informational_1xx += 1,
+                    300..=399 => report.redirect_3xx += 1,
+                    400..=499 => report.client_error_4xx += 1,
+                    500..=599 => report.server_error_5xx += 1,
+                    _ => {}
+                }
+                *report.status_counts.entry(status).or_insert(0) += 1;
+                *report.protocol_counts.entry(format!("{:?}", res.version())).or_insert(0) += 1;
 
                 // Set the server software
                 if report.server_software.is_empty() {
@@ -243,71 +1009,410 @@ impl Runner {
                     }
                 }
             }
-            Err(err) => {
-                let mut report = self.report.lock().unwrap();
-                report.failed_requests += 1; // increment number of failed requests
-                if err.kind() == ErrorKind::Timeout {
-                    // timeout was reached
-                    report.timeouts += 1;
-                }
-            }
+            Err(err) => self.record_failure(&err, url, adaptive_timeout),
         }
         Ok(())
     }
 
-    // std::Thread to stop the test after given duration (also prints and updates the elapsed time)
-    fn spawn_duration_thread(runner: Arc<Runner>, notify: Arc<Notify>) {
-        std::thread::spawn(move || {
-            let duration = runner.config.duration;
-            let start = Instant::now();
-            let mut last_printed_second = 0; // keep track of the last printed second
+    /// Read the response body, returns it (empty unless --expect-substring or --graphql check it) and its size
+    async fn read_body(&self, res: &mut Response<AsyncBody>) -> io::Result<(Vec<u8>, usize)> {
+        let checked = self.config.expect_substring.is_some() || self.config.graphql_body.is_some();
+        if checked {
+            let body = res.bytes().await?; // raw bytes, so non UTF-8 bodies still count as completed
+            let size = body.len();
+            return Ok((body, size));
+        }
+        // counted as it streams through, memory stays bounded whatever the size of the response
+        let size = res.copy_to(futures_lite::io::sink()).await?;
+        Ok((Vec::new(), size as usize))
+    }
 
-            while start.elapsed() <= duration {
-                // for printing progress
-                let elapsed = start.elapsed().as_secs(); // get elapsed time in seconds
-                if start.elapsed() < duration && elapsed > last_printed_second {
-                    last_printed_second = elapsed;
-                    print!("\rElapsed time: {}s", elapsed); // move to the start of line and print
-                    std::io::stdout().flush().unwrap(); // ensure the output is displayed immediately
-                }
-                runner.report.lock().unwrap().duration = start.elapsed(); // keep updating the test duration for ctrlc
-                std::thread::sleep(Duration::from_millis(10)); // delay to keep printing the progress
+    /// Count a failed request in the report (by kind, with timeouts, resets and DNS failures singled out)
+    fn record_failure(&self, err: &isahc::Error, url: &str, adaptive_timeout: Option<Duration>) {
+        let mut report = self.report.lock().unwrap();
+        report.failed_requests += 1; // increment number of failed requests
+        *report.errors_by_kind.entry(format!("{:?}", err.kind())).or_insert(0) += 1;
+        if err.kind() == ErrorKind::Timeout {
+            // timeout was reached
+            report.timeouts += 1;
+            if adaptive_timeout.is_some() {
+                report.adaptive_timeouts += 1; // killed by the adaptive deadline
             }
-            notify.notify_waiters();
-        });
+        } else if err.kind() == ErrorKind::NameResolution {
+            // attribute the failure to the host that didn't resolve
+            let host = Url::parse(url)
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_string))
+                .unwrap_or_default();
+            *report.dns_failures.entry(host).or_insert(0) += 1;
+        } else if Self::is_connection_reset(err) {
+            report.connection_resets += 1;
+        }
+        if self.exceeds_error_limits(&report) && !self.aborted.swap(true, Ordering::Relaxed) {
+            self.stop();
+        }
     }
 
-    /* ---------------------------------------------------------------------------
-     * std::Thread for running both tests, as soon as the total requests finishes
-     * a flag is set which terminates this thread, if above function is used
-     * an error will be generated on Arc::try_unwrap(runner)
-     * because of active references
-     * (also prints and updates the elapsed time)
-     * ------------------------------------------------------------------------ */
-    fn spawn_duration_thread_with_flag(
-        runner: Arc<Runner>,
-        notify: Arc<Notify>,
-        stop_flag: Arc<AtomicBool>
-    ) {
-        std::thread::spawn(move || {
-            let duration = runner.config.duration;
-            let start = Instant::now();
-            let mut last_printed_second = 0; // keep track of the last printed second
+    /// Metrics of a Server-Timing header value with their durations in ms (`db;dur=53, app;desc="x";dur=4.2`),
+    /// metrics without a duration are skipped
+    fn parse_server_timing(value: &str) -> Vec<(String, f64)> {
+        value
+            .split(',')
+            .filter_map(|metric| {
+                let mut params = metric.split(';').map(str::trim);
+                let name = params.next().filter(|name| !name.is_empty())?;
+                let duration = params
+                    .filter_map(|param| param.split_once('='))
+                    .find(|(key, _)| key.trim().eq_ignore_ascii_case("dur"))
+                    .and_then(|(_, duration)| duration.trim().trim_matches('"').parse::<f64>().ok())?;
+                Some((name.to_string(), duration))
+            })
+            .collect()
+    }
 
-            while start.elapsed() <= duration {
-                if stop_flag.load(Ordering::Relaxed) {
-                    return; // return immediately if the flag is set
-                }
+    /// Send a request, following the redirects --restrict-redirects allows (curl can't check where
+    /// a redirect leads, so it follows none), a blocked redirect is the response of the request.
+    /// Returns the final response with the metrics of the hops before it
+    async fn send_following_redirects(
+        &self,
+        client: &HttpClient,
+        method: &Method,
+        url: &str,
+        request_body: RequestBody<'_>,
+        build_request: &impl Fn(&Method, &str, RequestBody<'_>, bool) -> Result<Request<AsyncBody>, isahc::http::Error>
+    ) -> Result<(Response<AsyncBody>, Vec<Metrics>), isahc::Error> {
+        let mut res = client.send_async(build_request(method, url, request_body, true)?).await?;
+        let mut hops = Vec::new();
+        let (Ok(origin), true) = (Url::parse(url), self.config.follow_redirects) else {
+            return Ok((res, hops));
+        };
 
-                // for printing progress
+        let (mut current, mut method, mut request_body) = (origin.clone(), method.clone(), request_body);
+        let mut redirects = 0;
+        while let Some(location) = Self::redirect_location(&res, &current) {
+            if !self.config.restrict_redirects.allows(&current, &location) {
+                self.report.lock().unwrap().blocked_redirects += 1;
+                break;
+            }
+            if redirects >= self.config.max_redirects.unwrap_or(REDIRECT_LIMIT) {
+                return Err(isahc::Error::from(ErrorKind::TooManyRedirects));
+            }
+            redirects += 1;
+            res.consume().await?; // the connection is reused for the next hop
+            hops.extend(res.metrics().cloned()); // final once the body is consumed
+
+            // like curl, a 303 (or a 301 / 302 answering a POST) is sent on as a GET without the body
+            let status = res.status().as_u16();
+            if (status == 303 && method != Method::HEAD) || (matches!(status, 301 | 302) && method == Method::POST) {
+                method = Method::GET;
+                request_body = None;
+            }
+            let request = build_request(&method, location.as_str(), request_body, location.origin() == origin.origin())?;
+            res = client.send_async(request).await?;
+            current = location;
+        }
+        Ok((res, hops))
+    }
+
+    /// Where a redirect response points to, resolved against the url it answered
+    fn redirect_location(res: &Response<AsyncBody>, url: &Url) -> Option<Url> {
+        if !matches!(res.status().as_u16(), 301 | 302 | 303 | 307 | 308) {
+            return None;
+        }
+        let location = res.headers().get("location")?.to_str().ok()?;
+        url.join(location).ok().filter(|url| matches!(url.scheme(), "http" | "https"))
+    }
+
+    /// Whether a failure may pass when sent again (--retries)
+    fn is_transient(err: &isahc::Error) -> bool {
+        matches!(err.kind(), ErrorKind::ConnectionFailed | ErrorKind::Timeout) || Self::is_connection_reset(err)
+    }
+
+    /// Whether the server reset or closed the connection (isahc reports these as Io or ProtocolViolation)
+    fn is_connection_reset(err: &isahc::Error) -> bool {
+        let Some(source) = std::error::Error::source(err) else {
+            return false;
+        };
+        if let Some(err) = source.downcast_ref::<curl::Error>() {
+            // recv / send failures are resets, got nothing and partial file an early EOF
+            return err.is_recv_error() || err.is_send_error() || err.is_got_nothing() || err.is_partial_file();
+        }
+        source.downcast_ref::<io::Error>().is_some_and(|err| {
+            matches!(
+                err.kind(),
+                io::ErrorKind::ConnectionReset |
+                    io::ErrorKind::ConnectionAborted |
+                    io::ErrorKind::BrokenPipe |
+                    io::ErrorKind::UnexpectedEof
+            )
+        })
+    }
+
+    /// Whether the failures so far exceed --max-errors or --max-error-rate
+    fn exceeds_error_limits(&self, report: &Report) -> bool {
+        let failed = report.failed_requests;
+        let finished = report.completed_requests + failed;
+        let too_many = self.config.max_errors.is_some_and(|max_errors| failed > max_errors);
+        let too_often = self.config.max_error_rate.is_some_and(|max_error_rate| {
+            finished >= ERROR_RATE_CALIBRATION &&
+                ((failed as f64) / (finished as f64)) * 100.0 > max_error_rate
+        });
+        too_many || too_often
+    }
+
+    /// Pick the url for the next request, round-robin across config.urls
+    fn next_url(&self) -> &str {
+        let index = self.next_url.fetch_add(1, Ordering::Relaxed);
+        &self.config.urls[index % self.config.urls.len()]
+    }
+
+    /// Pick the --script request to send next with its index, round-robin or by weight (None without a script)
+    fn next_spec(&self) -> Option<(usize, &RequestSpec)> {
+        let script = &self.config.script;
+        if script.is_empty() {
+            return None;
+        }
+        let total_weight: usize = script.iter().map(|spec| spec.weight).sum();
+        if total_weight == script.len() {
+            let index = self.next_url.fetch_add(1, Ordering::Relaxed) % script.len();
+            return Some((index, &script[index])); // no weights given
+        }
+        let mut pick = self.rng.lock().unwrap().gen_range(0..total_weight);
+        script.iter().enumerate().find(|(_, spec)| {
+            if pick < spec.weight {
+                return true;
+            }
+            pick -= spec.weight;
+            false
+        })
+    }
+
+    /// Whether a response passes --expect-status and --expect-substring
+    fn is_expected_response(&self, status: u16, body: &[u8]) -> bool {
+        if !self.config.expect_status.is_empty() && !self.config.expect_status.contains(&status) {
+            return false;
+        }
+        match &self.config.expect_substring {
+            Some(substring) => body.windows(substring.len()).any(|window| window == substring.as_bytes()),
+            None => true,
+        }
+    }
+
+    /// Whether a GraphQL response body carries a non-empty top-level "errors" field
+    fn has_graphql_errors(body: &[u8]) -> bool {
+        serde_json
+            ::from_slice::<serde_json::Value>(body)
+            .ok()
+            .and_then(|value| value.get("errors").cloned())
+            .is_some_and(|errors| !errors.is_null() && errors.as_array().is_none_or(|e| !e.is_empty()))
+    }
+
+    /// Pause for a random time within the --think-time range (before the latency clock starts)
+    async fn think(&self) {
+        let Some((min, max)) = self.config.think_time else {
+            return;
+        };
+        let pause = self.rng.lock().unwrap().gen_range(min..=max);
+        sleep(pause).await;
+    }
+
+    /// Wait for the next dispatch slot with --rate, slots are 1/rate apart across all tasks
+    /// (returns the slot the request was scheduled for, None without a rate)
+    async fn wait_for_rate_slot(&self) -> Option<Instant> {
+        let rate = self.config.rate?;
+        let interval = Duration::from_secs_f64(1.0 / (rate as f64));
+        let slot = {
+            let mut next_slot = self.next_slot.lock().unwrap();
+            let now = Instant::now();
+            // a slot in the past means the server can't keep up, send right away instead of bursting to catch up,
+            // unless correcting for coordinated omission, then the schedule is kept (and late requests burst)
+            let slot = if self.config.correct_co {
+                next_slot.unwrap_or(now)
+            } else {
+                next_slot.map_or(now, |next| next.max(now))
+            };
+            *next_slot = Some(slot + interval);
+            slot
+        };
+        sleep_until(slot).await;
+        Some(slot)
+    }
+
+    /// Progress of a request count test, the ETA assumes the rate so far holds
+    fn print_request_progress(&self, completed: usize, elapsed: Duration) {
+        let total = self.config.requests;
+        let remaining = total.saturating_sub(completed);
+        let eta = elapsed.as_secs_f64() * (remaining as f64) / (completed.max(1) as f64);
+        self.print_progress(
+            (completed as f64) / (total as f64),
+            &format!("{}/{} requests, ETA {:.0}s", completed, total, eta)
+        );
+    }
+
+    /// Render the progress bar on a single updating line, e.g `[#####-----]  50% 5/10s`
+    fn print_progress(&self, fraction: f64, detail: &str) {
+        let fraction = fraction.clamp(0.0, 1.0);
+        let filled = (fraction * (PROGRESS_WIDTH as f64)) as usize;
+        let live_stats = self.live_stats.lock().unwrap().clone();
+        let mut out = self.status_out();
+        // move to the start of line and print, padded to wipe a longer previous line
+        let _ = write!(
+            out,
+            "\r[{}{}] {:>3.0}% {:<32}{:<LIVE_STATS_WIDTH$}",
+            "#".repeat(filled),
+            "-".repeat(PROGRESS_WIDTH - filled),
+            fraction * 100.0,
+            detail,
+            live_stats
+        );
+        let _ = out.flush(); // ensure the output is displayed immediately
+    }
+
+    /// Render the req/s, p50 and p99 of the latencies recorded since the last call (with --live),
+    /// `window` is swapped with the report's one so the report lock is held only for the swap
+    fn update_live_stats(&self, window: &mut Histogram<u64>, last_completed: &mut usize) {
+        let completed = {
+            let mut report = self.report.lock().unwrap();
+            let Some(live_latencies) = report.live_latencies.as_mut() else {
+                return;
+            };
+            std::mem::swap(live_latencies, window);
+            report.completed_requests
+        };
+        let per_sec = completed.saturating_sub(*last_completed);
+        *last_completed = completed;
+
+        let unit = self.config.latency_unit;
+        *self.live_stats.lock().unwrap() = if window.is_empty() {
+            format!("  {} req/s", per_sec)
+        } else {
+            format!(
+                "  {} req/s  p50 {}  p99 {}",
+                per_sec,
+                Self::format_latency(Report::histogram_percentile(window, 50.0), unit),
+                Self::format_latency(Report::histogram_percentile(window, 99.0), unit)
+            )
+        };
+        window.reset();
+    }
+
+    /// Append a sent request to the recording as a JSON line with its method, url, headers
+    /// and the sha256 of its body (null without one)
+    fn record_request(&self, method: &str, url: &str, body: Option<(&[u8], Option<&str>)>) {
+        let mut headers: Vec<(&str, &str)> = self.config.headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        if let Some((_, Some(content_type))) = body {
+            headers.push(("content-type", content_type));
+        }
+        let body_sha256 = body.map(|(body, _)| {
+            openssl::sha::sha256(body).iter().map(|byte| format!("{:02x}", byte)).collect::<String>()
+        });
+        let line = serde_json::json!({
+            "method": method,
+            "url": url,
+            "headers": headers,
+            "body_sha256": body_sha256,
+        });
+        if let Some(writer) = self.recorder.lock().unwrap().as_mut() {
+            let _ = writeln!(writer, "{}", line);
+        }
+    }
+
+    /// Append a completed request to the raw csv as `index,latency_ms,status,bytes` (after the name with --name)
+    fn write_raw_row(&self, latency: Duration, status: u16, bytes: usize) {
+        if let Some(writer) = self.raw_csv.lock().unwrap().as_mut() {
+            let index = self.raw_rows.fetch_add(1, Ordering::Relaxed);
+            let _ = writeln!(
+                writer,
+                "{}{},{:.3},{},{}",
+                self.csv_name(),
+                index,
+                latency.as_secs_f64() * 1000.0,
+                status,
+                bytes
+            );
+        }
+    }
+
+    // leading `name,` field of the csv rows with --name (quoted if needed), empty otherwise
+    fn csv_name(&self) -> String {
+        match &self.config.name {
+            Some(name) if name.contains([',', '"', '\n', '\r']) => format!("\"{}\",", name.replace('"', "\"\"")),
+            Some(name) => format!("{},", name),
+            None => String::new(),
+        }
+    }
+
+    // std::Thread to stop the test after given duration (also prints and updates the elapsed time)
+    fn spawn_duration_thread(runner: Arc<Runner>, notify: Arc<Notify>) {
+        std::thread::spawn(move || {
+            let duration = runner.config.duration;
+            let start = Instant::now();
+            let mut last_printed_second = 0; // keep track of the last printed second
+            let mut live_window = Report::new_histogram(); // swapped with the report's one each second (with --live)
+            let mut live_completed = runner.report.lock().unwrap().completed_requests;
+
+            while start.elapsed() <= duration && !runner.is_stopping() {
+                // for printing progress
+                let elapsed = start.elapsed().as_secs(); // get elapsed time in seconds
+                if start.elapsed() < duration && elapsed > last_printed_second {
+                    last_printed_second = elapsed;
+                    runner.update_live_stats(&mut live_window, &mut live_completed);
+                    runner.print_progress(
+                        (elapsed as f64) / duration.as_secs_f64(),
+                        &format!("{}s/{:?}", elapsed, duration)
+                    );
+                }
+                let mut report = runner.report.lock().unwrap();
+                report.duration = start.elapsed(); // keep updating the test duration for ctrlc
+                report.sample_throughput(); // per-second throughput samples
+                drop(report);
+                std::thread::sleep(Duration::from_millis(10)); // delay to keep printing the progress
+            }
+            notify.notify_waiters();
+        });
+    }
+
+    /* ---------------------------------------------------------------------------
+     * std::Thread for running both tests, as soon as the total requests finishes
+     * a flag is set which terminates this thread, if above function is used
+     * Arc::try_unwrap(runner) would fail because of active references
+     * (also prints and updates the elapsed time)
+     * ------------------------------------------------------------------------ */
+    fn spawn_duration_thread_with_flag(
+        runner: Arc<Runner>,
+        notify: Arc<Notify>,
+        stop_flag: Arc<AtomicBool>
+    ) {
+        std::thread::spawn(move || {
+            let duration = runner.config.duration;
+            let start = Instant::now();
+            let mut last_printed_second = 0; // keep track of the last printed second
+            let mut live_window = Report::new_histogram(); // swapped with the report's one each second (with --live)
+            let mut live_completed = runner.report.lock().unwrap().completed_requests;
+
+            while start.elapsed() <= duration && !runner.is_stopping() {
+                if stop_flag.load(Ordering::Relaxed) {
+                    return; // return immediately if the flag is set
+                }
+
+                // for printing progress
                 let elapsed = start.elapsed().as_secs(); // get elapsed time in seconds
                 if start.elapsed() < duration && elapsed > last_printed_second {
                     last_printed_second = elapsed;
-                    print!("\rElapsed time: {}s", elapsed); // move to the start of line and print
-                    std::io::stdout().flush().unwrap(); // ensure the output is displayed immediately
+                    runner.update_live_stats(&mut live_window, &mut live_completed);
+                    runner.print_progress(
+                        (elapsed as f64) / duration.as_secs_f64(),
+                        &format!("{}s/{:?}", elapsed, duration)
+                    );
                 }
 
-                runner.report.lock().unwrap().duration = start.elapsed(); // keep updating the test duration for ctrlc
+                let mut report = runner.report.lock().unwrap();
+                report.duration = start.elapsed(); // keep updating the test duration for ctrlc
+                report.sample_throughput(); // per-second throughput samples
+                drop(report);
                 std::thread::sleep(Duration::from_millis(10)); // small delay to keep checking for flag
             }
             // Otherwise notify waiters
@@ -319,288 +1424,2094 @@ impl Runner {
     fn spawn_timer_thread(runner: Arc<Runner>, stop_flag: Arc<AtomicBool>) {
         std::thread::spawn(move || {
             let start = Instant::now();
+            let mut last_second = 0;
+            let mut live_window = Report::new_histogram(); // swapped with the report's one each second (with --live)
+            let mut live_completed = runner.report.lock().unwrap().completed_requests;
 
             loop {
                 if stop_flag.load(Ordering::Relaxed) {
                     return; // return immediately if the flag is set
                 }
 
-                runner.report.lock().unwrap().duration = start.elapsed(); // keep updating the test duration for ctrlc
+                // the request pool prints the progress, only refresh the stats it appends
+                if start.elapsed().as_secs() > last_second {
+                    last_second = start.elapsed().as_secs();
+                    runner.update_live_stats(&mut live_window, &mut live_completed);
+                }
+
+                let mut report = runner.report.lock().unwrap();
+                report.duration = start.elapsed(); // keep updating the test duration for ctrlc
+                report.sample_throughput(); // per-second throughput samples
+                let deadline = runner.config.max_duration.filter(|&max| start.elapsed() >= max);
+                if let Some(max_duration) = deadline.filter(|_| !runner.is_stopping()) {
+                    let warning = format!(
+                        "stopped after --max-duration {:?}, {} of {} requests finished",
+                        max_duration,
+                        report.completed_requests + report.failed_requests,
+                        runner.config.requests
+                    );
+                    let _ = writeln!(runner.status_out(), "\nStopping, --max-duration {:?} elapsed", max_duration);
+                    report.warnings.push(warning);
+                    report.cut_short = true;
+                    runner.stop(); // the in-flight requests are abandoned like on Ctrl+C
+                }
+                drop(report);
                 std::thread::sleep(Duration::from_millis(10)); // delay to keep printing the progress
             }
         });
     }
 
-    /// Helper function to create tokio Arc runtime
-    fn get_arc_runtime(threads: &usize) -> Arc<Runtime> {
-        Arc::new(
-            Builder::new_multi_thread()
-                .worker_threads(*threads)
-                .enable_all()
-                .build()
-                .expect("Failed to create tokio runtime")
-        )
+    /// Helper function to create tokio Arc runtime
+    fn get_arc_runtime(config: &Config) -> Arc<Runtime> {
+        let mut builder = Builder::new_multi_thread();
+        builder.worker_threads(config.threads).enable_all();
+        // named for perf / tokio-console, linux truncates thread names to 15 bytes so keep it short
+        let thread_index = AtomicUsize::new(0);
+        builder.thread_name_fn(move || format!("benchener-{}", thread_index.fetch_add(1, Ordering::Relaxed)));
+        if let Some(max_blocking_threads) = config.max_blocking_threads {
+            builder.max_blocking_threads(max_blocking_threads); // tokio defaults to 512
+        }
+        Arc::new(builder.build().expect("Failed to create tokio runtime"))
+    }
+
+    /// Check if the URL is reachable before running tests
+    fn is_url_reachable(&self, url: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let parsed_url = Url::parse(url).map_err(|e| format!("Invalid URL: {}", e))?;
+        let hostname = parsed_url
+            .host_str()
+            .ok_or_else(|| "URL does not have a valid hostname".to_string())?;
+        let port = parsed_url.port_or_known_default().unwrap_or(80); // HTTP port 80 if none sepcified
+
+        // set the hostname and port in report (of the main url when there are several)
+        let mut report = self.report.lock().unwrap();
+        if url == self.config.url {
+            report.host = hostname.to_string();
+            report.port = port;
+        }
+
+        let address = Self::socket_address(&parsed_url, &self.config.resolve).ok_or_else(|| "URL does not have a valid hostname".to_string())?;
+        match Self::connect(&address, self.config.connection_timeout) {
+            Ok(stream) => {
+                // reuse the connection for inspecting the certificate
+                if let Some(window_days) = self.config.cert_expiry_days {
+                    if parsed_url.scheme() == "https" {
+                        // host_str keeps the brackets of an IPv6 literal, the TLS server name must not have them
+                        let server_name = hostname.trim_start_matches('[').trim_end_matches(']');
+                        let warning = match Self::get_cert_expiry(stream, server_name) {
+                            Ok((expiry, days_left)) => {
+                                report.cert_expiry = Some(expiry.clone());
+                                if days_left < 0 {
+                                    Some(format!("certificate for {} expired on {}", hostname, expiry))
+                                } else if (days_left as usize) <= window_days {
+                                    Some(
+                                        format!(
+                                            "certificate for {} expires in {} day(s) on {}",
+                                            hostname,
+                                            days_left,
+                                            expiry
+                                        )
+                                    )
+                                } else {
+                                    None
+                                }
+                            }
+                            Err(e) => Some(format!("could not check certificate expiry: {}", e)),
+                        };
+                        if let Some(warning) = warning {
+                            let _ = writeln!(self.status_out(), "Warning: {}", warning);
+                            report.warnings.push(warning);
+                        }
+                    }
+                }
+
+                Ok(())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Print what the test is about to do (the plan is printed instead with --dry-run)
+    fn print_banner(&self) {
+        let report = self.report.lock().unwrap();
+        let mut out = self.status_out();
+        if self.config.test_type == TestType::RequestCount {
+            let _ = writeln!(out, "Sending {} request(s) to {}", self.config.requests, self.config.url);
+        } else if self.config.test_type == TestType::Duration {
+            let _ = writeln!(
+                out,
+                "Running {:?} test on {}",
+                self.config.duration,
+                self.config.url
+            );
+        } else {
+            let _ = writeln!(
+                out,
+                "Sending {} request(s) to {} in {:?}",
+                self.config.requests,
+                self.config.url,
+                self.config.duration
+            );
+        }
+        if self.config.urls.len() > 1 {
+            let _ = writeln!(out, "round-robin across {} url(s)", self.config.urls.len());
+        }
+        if !self.config.stages.is_empty() {
+            let _ = writeln!(out, "in {} stage(s), up to concurrency {}", self.config.stages.len(), self.config.concurrency);
+        }
+        if let Some(ramp) = &self.config.ramp {
+            let _ = writeln!(out, "ramping concurrency from {} to {} over {:?}", ramp.start, ramp.end, ramp.duration);
+        }
+        if let (Some(connections), Some(pipeline)) = (self.config.connections, self.config.pipeline) {
+            let _ = writeln!(
+                out,
+                "using {} thread(s) and {} concurrent request(s) ({} connection(s) x {} pipeline depth)",
+                self.config.threads,
+                self.config.concurrency,
+                connections,
+                pipeline
+            );
+        } else if let Some(connections) = self.config.connections {
+            let _ = writeln!(
+                out,
+                "using {} thread(s) and {} concurrent request(s) over {} connection(s)",
+                self.config.threads,
+                self.config.concurrency,
+                connections
+            );
+        } else {
+            let _ = writeln!(
+                out,
+                "using {} thread(s) and {} concurrent request(s)",
+                self.config.threads,
+                self.config.concurrency
+            );
+        }
+        if self.config.no_keepalive {
+            let _ = writeln!(out, "keep-alive disabled, every request opens a new connection");
+        }
+        if let Some(rate) = self.config.rate {
+            let _ = writeln!(out, "rate limited to {} request(s) per sec", rate);
+        }
+        if let Some((min, max)) = self.config.think_time {
+            let _ = writeln!(
+                out,
+                "pausing {}-{}ms before each request",
+                min.as_millis(),
+                max.as_millis()
+            );
+        }
+        if let Some(fd_limit) = report.fd_limit {
+            let _ = writeln!(out, "open file limit: {}", fd_limit);
+        }
+        let _ = writeln!(out, "seed: {} (replay with --seed)", report.seed);
+        let _ = writeln!(out, "Please be patient..");
+    }
+
+    /// One url per distinct host:port the test sends requests to (the urls and the --script requests)
+    fn targets(&self) -> Vec<&str> {
+        let mut seen = Vec::new();
+        let script_urls = self.config.script.iter().map(|spec| &spec.url);
+        let mut targets = Vec::new();
+        for url in std::iter::once(&self.config.url).chain(&self.config.urls).chain(script_urls) {
+            let address = Url::parse(url).ok().and_then(|url| Self::socket_address(&url, &self.config.resolve));
+            if !seen.contains(&address) {
+                seen.push(address);
+                targets.push(url.as_str());
+            }
+        }
+        targets
+    }
+
+    /// Connect to the first resolved address that accepts, each attempt bounded by --connection-timeout,
+    /// the error tells a DNS failure apart from a host that resolved but did not accept the connection
+    fn connect(address: &str, timeout: Duration) -> Result<TcpStream, String> {
+        let addrs = address
+            .to_socket_addrs()
+            .map_err(|e| format!("DNS resolution of {} failed: {}", address, e))?;
+        let mut last_error = format!("DNS resolution of {} returned no address", address);
+        for addr in addrs {
+            match TcpStream::connect_timeout(&addr, timeout) {
+                Ok(stream) => return Ok(stream),
+                Err(e) => {
+                    last_error = match e.kind() {
+                        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock =>
+                            format!("connection to {} timed out after {}s", addr, timeout.as_secs_f64()),
+                        io::ErrorKind::ConnectionRefused =>
+                            format!("connection refused by {} (nothing is listening on the port)", addr),
+                        io::ErrorKind::HostUnreachable | io::ErrorKind::NetworkUnreachable =>
+                            format!("{} is unreachable (no route to the host)", addr),
+                        _ => format!("failed to connect to {}: {}", addr, e),
+                    };
+                }
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Print the resolved settings of the test (for --dry-run)
+    fn print_plan(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "Dry run, no requests will be sent")?;
+        writeln!(out)?;
+        self.print_settings(out, true)?;
+        if let Some(fd_limit) = self.report.lock().unwrap().fd_limit {
+            writeln!(out, "{:<24}{}", "Open File Limit:", fd_limit)?;
+        }
+        Ok(())
+    }
+
+    /// Print the options the test runs with (the plan of --dry-run and the top of the full report),
+    /// header values are left out of the report as they may carry credentials
+    fn print_settings(&self, out: &mut dyn Write, header_values: bool) -> io::Result<()> {
+        let config = &self.config;
+        if let Some(name) = &config.name {
+            writeln!(out, "{:<24}{}", "Name:", name)?;
+        }
+        if config.script.is_empty() {
+            writeln!(out, "{:<24}{}", "Method:", config.request_method())?;
+            for (i, url) in config.urls.iter().enumerate() {
+                writeln!(out, "{:<24}{}", if i == 0 { "URL(s):" } else { "" }, url)?;
+            }
+            if let Some(sampled) = config.sampled_urls() {
+                writeln!(out, "{:<24}{} of the {} in --urls-file", "Sampled URLs:", sampled.len(), config.urls_in_files)?;
+            }
+        }
+        for (i, spec) in config.script.iter().enumerate() {
+            let body = spec.body.as_ref().map_or(String::new(), |body| format!(" ({} bytes)", body.len()));
+            writeln!(
+                out,
+                "{:<24}{} {} {}{}",
+                if i == 0 { "Script:" } else { "" },
+                spec.weight,
+                spec.method,
+                spec.url,
+                body
+            )?;
+        }
+        for (i, (host, port, address)) in config.resolve.iter().enumerate() {
+            writeln!(out, "{:<24}{}:{} -> {}", if i == 0 { "Resolve:" } else { "" }, host, port, address)?;
+        }
+        let mut headers = config.headers.clone();
+        if let Some((_, Some(content_type))) = config.request_body() {
+            headers.push(("content-type".to_string(), content_type.to_string()));
+        }
+        if headers.is_empty() {
+            writeln!(out, "{:<24}none", "Headers:")?;
+        } else if !header_values {
+            writeln!(out, "{:<24}{}", "Headers:", headers.len())?;
+        } else {
+            for (i, (name, value)) in headers.iter().enumerate() {
+                writeln!(out, "{:<24}{}: {}", if i == 0 { "Headers:" } else { "" }, name, value)?;
+            }
+        }
+        if config.cookies {
+            writeln!(out, "{:<24}kept, {} seeded", "Cookies:", config.seed_cookies.len())?;
+        }
+        match config.request_body() {
+            Some((body, _)) => writeln!(out, "{:<24}{} bytes", "Body:", body.len())?,
+            None => writeln!(out, "{:<24}none", "Body:")?,
+        }
+        writeln!(out)?;
+
+        if config.test_type != TestType::Duration {
+            writeln!(out, "{:<24}{}", "Requests:", config.requests)?;
+        }
+        if let Some(max_duration) = config.max_duration {
+            writeln!(out, "{:<24}{:?}", "Max duration:", max_duration)?;
+        }
+        if config.test_type != TestType::RequestCount {
+            writeln!(out, "{:<24}{:?}", "Duration:", config.duration)?;
+        }
+        match &config.warmup {
+            Some(Warmup::Requests(requests)) => writeln!(out, "{:<24}{} request(s)", "Warmup:", requests)?,
+            Some(Warmup::Duration(duration)) => writeln!(out, "{:<24}{:?}", "Warmup:", duration)?,
+            None => {}
+        }
+        match &config.ramp {
+            Some(ramp) => writeln!(out, "{:<24}{} to {} over {:?}", "Concurrency:", ramp.start, ramp.end, ramp.duration)?,
+            None => writeln!(out, "{:<24}{}", "Concurrency:", config.concurrency)?,
+        }
+        match config.connections {
+            Some(connections) => writeln!(out, "{:<24}{}", "Connections:", connections)?,
+            None => writeln!(out, "{:<24}unbounded", "Connections:")?,
+        }
+        writeln!(out, "{:<24}{}", "Threads:", config.threads)?;
+        match config.rate {
+            Some(rate) => writeln!(out, "{:<24}{} request(s) per sec", "Rate:", rate)?,
+            None => writeln!(out, "{:<24}unlimited", "Rate:")?,
+        }
+        if let Some((min, max)) = config.think_time {
+            writeln!(out, "{:<24}{:?}-{:?}", "Think Time:", min, max)?;
+        }
+        writeln!(out, "{:<24}{:?}", "Timeout:", config.timeout)?;
+        writeln!(out, "{:<24}{:?}", "Connection Timeout:", config.connection_timeout)?;
+        if config.retries > 0 {
+            writeln!(out, "{:<24}{} (backoff {:?})", "Retries:", config.retries, config.retry_backoff)?;
+        }
+        Ok(())
+    }
+
+    /// Sink for progress and banner output, stderr when stdout carries a machine readable report
+    /// or the report goes into a file (discarded with --quiet)
+    fn status_out(&self) -> Box<dyn Write> {
+        if self.config.quiet {
+            Box::new(io::sink())
+        } else if self.config.output.is_machine() || self.config.out_file.is_some() {
+            Box::new(io::stderr())
+        } else {
+            Box::new(io::stdout())
+        }
+    }
+
+    /// Write the report in the configured format, plus the human readable one to stderr with --human-stderr
+    fn write_report(&self, out: &mut dyn Write) -> io::Result<()> {
+        match self.config.output {
+            OutputFormat::Text => self.write_human_report(out)?,
+            OutputFormat::Breakdown => self.print_phase_breakdown(out)?,
+            OutputFormat::Json => self.print_json_report(out)?,
+            OutputFormat::Prometheus => self.print_prometheus_report(out, false)?,
+            OutputFormat::OpenMetrics => self.print_prometheus_report(out, true)?,
+            OutputFormat::Markdown => self.print_markdown_report(out)?,
+        }
+        if self.config.human_stderr {
+            self.write_human_report(&mut io::stderr().lock())?;
+        }
+        Ok(())
+    }
+
+    /// Advisory warnings about the health of the run (raised during the run or derived from the report)
+    fn warnings(&self) -> Vec<String> {
+        let report = self.report.lock().unwrap();
+        let mut warnings = report.warnings.clone();
+
+        if !report.queue_times.is_empty() {
+            let p99 = Report::histogram_percentile(&report.queue_times, 99.0);
+            if p99 >= QUEUE_SATURATION_MS {
+                warnings.push(format!("client connection pool saturated (p99 queue time {:.2}ms)", p99));
+            }
+        }
+        warnings
+    }
+
+    /// `host:port` of the url for connecting to it, IPv6 literals are bracketed (`[::1]:8080`),
+    /// a host pinned with --resolve is replaced by its address like the client does
+    fn socket_address(url: &Url, resolve: &[(String, u16, IpAddr)]) -> Option<String> {
+        let port = url.port_or_known_default().unwrap_or(80); // HTTP port 80 if none specified
+        match url.host()? {
+            Host::Domain(domain) => {
+                let pinned = resolve.iter().find(|(host, pinned_port, _)| host == domain && *pinned_port == port);
+                match pinned {
+                    Some((_, _, address)) => Some(SocketAddr::new(*address, port).to_string()),
+                    None => Some(format!("{}:{}", domain, port)),
+                }
+            }
+            Host::Ipv4(ip) => Some(format!("{}:{}", ip, port)),
+            Host::Ipv6(ip) => Some(format!("[{}]:{}", ip, port)),
+        }
+    }
+
+    /// Returns the expiry date of the server certificate and the days left until then
+    fn get_cert_expiry(
+        stream: TcpStream,
+        hostname: &str
+    ) -> Result<(String, i32), Box<dyn std::error::Error>> {
+        let mut builder = SslConnector::builder(SslMethod::tls())?;
+        builder.set_verify(SslVerifyMode::NONE); // only inspecting, an expired certificate must not fail the handshake
+        let ssl_stream = builder.build().connect(hostname, stream)?;
+
+        let cert = ssl_stream
+            .ssl()
+            .peer_certificate()
+            .ok_or_else(|| "server did not present a certificate".to_string())?;
+        let days_left = Asn1Time::days_from_now(0)?.diff(cert.not_after())?.days;
+
+        Ok((cert.not_after().to_string(), days_left))
+    }
+
+    /*---------= Everything related to printing =----------*/
+    /// Write the mean time spent in each request phase as a stacked bar
+    fn print_phase_breakdown(&self, out: &mut dyn Write) -> io::Result<()> {
+        write!(out, "\n\n")?;
+
+        let report = self.report.lock().unwrap();
+        let phases = &report.phases;
+        if phases.samples == 0 {
+            return writeln!(out, "No phase timings were recorded");
+        }
+
+        let samples = phases.samples as f64;
+        let means = [
+            ('D', "DNS", phases.dns / samples),
+            ('C', "Connect", phases.connect / samples),
+            ('T', "TLS", phases.tls / samples),
+            ('S', "Server", phases.server / samples),
+            ('X', "Transfer", phases.transfer / samples),
+        ];
+        let total: f64 = means.iter().map(|(_, _, mean)| mean).sum();
+
+        let mut bar = String::new();
+        for (symbol, _, mean) in &means {
+            let width = if total > 0.0 {
+                ((mean / total) * (BREAKDOWN_WIDTH as f64)).round() as usize
+            } else {
+                0
+            };
+            bar.push_str(&symbol.to_string().repeat(width));
+        }
+
+        let unit = self.config.latency_unit;
+        writeln!(
+            out,
+            "Request Breakdown (mean {}{} over {} requests):",
+            unit.format(total),
+            unit.label(),
+            phases.samples
+        )?;
+        writeln!(out, " [{}]", bar)?;
+        for (symbol, name, mean) in &means {
+            let share = if total > 0.0 { (mean / total) * 100.0 } else { 0.0 };
+            writeln!(out, " {} {:<10} {:>10}{} {:>6.1}%", symbol, name, unit.format(*mean), unit.label(), share)?;
+        }
+        Ok(())
+    }
+
+    /// Serialize the report summary as a single JSON document
+    fn print_json_report(&self, out: &mut dyn Write) -> io::Result<()> {
+        let summary = self.json_summary();
+        serde_json::to_writer_pretty(&mut *out, &summary)?;
+        writeln!(out)
+    }
+
+    /// Final metrics in the Prometheus text exposition format, from the same summary as the json report.
+    /// As OpenMetrics the families carry their unit, the latency buckets link to a request id and it ends with # EOF
+    fn print_prometheus_report(&self, out: &mut dyn Write, openmetrics: bool) -> io::Result<()> {
+        let summary = self.json_summary();
+        let started = self.report.lock().unwrap().started_utc.and_then(|time| time.duration_since(UNIX_EPOCH).ok());
+        // every sample is labelled with the url, and the name of the run with --name
+        let labels = match &summary.name {
+            Some(name) => format!("url=\"{}\",name=\"{}\"", Self::escape_label(&summary.url), Self::escape_label(name)),
+            None => format!("url=\"{}\"", Self::escape_label(&summary.url)),
+        };
+        let family = |out: &mut dyn Write, name: &str, kind: &str, unit: &str, help: &str| {
+            Self::write_metric_family(out, openmetrics, name, kind, unit, help)
+        };
+
+        family(out, "benchener_requests", "counter", "", "Requests finished during the test.")?;
+        writeln!(out, "benchener_requests_total{{{},outcome=\"completed\"}} {}", labels, summary.completed_requests)?;
+        writeln!(out, "benchener_requests_total{{{},outcome=\"failed\"}} {}", labels, summary.failed_requests)?;
+
+        family(out, "benchener_errors", "counter", "", "Failed requests by kind of error.")?;
+        for (kind, count) in &summary.errors_by_kind {
+            writeln!(out, "benchener_errors_total{{{},kind=\"{}\"}} {}", labels, Self::snake_case(kind), count)?;
+        }
+
+        family(out, "benchener_responses", "counter", "", "Responses by status code.")?;
+        for (status, count) in &summary.status_counts {
+            writeln!(out, "benchener_responses_total{{{},code=\"{}\"}} {}", labels, status, count)?;
+        }
+
+        // cumulative buckets of the latency histogram (the report keeps it in µs)
+        family(out, "benchener_request_duration_seconds", "histogram", "seconds", "Latency of the completed requests.")?;
+        {
+            let report = self.report.lock().unwrap();
+            // OpenMetrics wants the bounds as canonical floats (1.0, not 1)
+            let bounds = PROMETHEUS_BUCKETS
+                .iter()
+                .map(|&le| (le, if openmetrics { format!("{:?}", le) } else { le.to_string() }))
+                .chain([(f64::INFINITY, "+Inf".to_string())]);
+            for (bucket, (le, bound)) in bounds.enumerate() {
+                let count = if le.is_finite() {
+                    report.latencies.count_between(0, (le * 1_000_000.0) as u64)
+                } else {
+                    report.latency_count() as u64
+                };
+                write!(out, "benchener_request_duration_seconds_bucket{{{},le=\"{}\"}} {}", labels, bound, count)?;
+                if let (true, Some(exemplar)) = (openmetrics, report.latency_exemplars.get(&bucket)) {
+                    let at = exemplar.at.duration_since(UNIX_EPOCH).unwrap_or_default();
+                    write!(
+                        out,
+                        " # {{request_id=\"{}\"}} {} {:.3}",
+                        Self::escape_label(&exemplar.request_id),
+                        exemplar.latency.as_secs_f64(),
+                        at.as_secs_f64()
+                    )?;
+                }
+                writeln!(out)?;
+            }
+            let count = report.latency_count();
+            writeln!(out, "benchener_request_duration_seconds_sum{{{}}} {}", labels, report.latency_total_ms / 1000.0)?;
+            writeln!(out, "benchener_request_duration_seconds_count{{{}}} {}", labels, count)?;
+        }
+
+        family(
+            out,
+            "benchener_request_duration_percentile_seconds",
+            "gauge",
+            "seconds",
+            "Latency at the --percentiles."
+        )?;
+        for (name, ms) in &summary.percentiles_ms {
+            let percentile = name.trim_start_matches('p');
+            writeln!(
+                out,
+                "benchener_request_duration_percentile_seconds{{{},percentile=\"{}\"}} {}",
+                labels,
+                percentile,
+                ms / 1000.0
+            )?;
+        }
+
+        if let Some(started) = started {
+            family(out, "benchener_start_time_seconds", "gauge", "seconds", "Start of the run since the unix epoch.")?;
+            writeln!(out, "benchener_start_time_seconds{{{}}} {}", labels, started.as_secs())?;
+        }
+
+        let gauges = [
+            ("test_duration_seconds", "seconds", "Duration of the test.", summary.duration_secs),
+            ("requests_per_second", "", "Completed requests per second.", summary.requests_per_sec),
+            ("concurrency", "", "Requests in flight at a time.", summary.concurrency as f64),
+        ];
+        for (name, unit, help, value) in gauges {
+            family(out, &format!("benchener_{}", name), "gauge", unit, help)?;
+            writeln!(out, "benchener_{}{{{}}} {}", name, labels, value)?;
+        }
+        family(out, "benchener_received_bytes", "counter", "bytes", "Response bodies (as sent) and headers read.")?;
+        writeln!(out, "benchener_received_bytes_total{{{}}} {}", labels, (summary.total_transferred_kb * 1024.0).round())?;
+        if openmetrics {
+            writeln!(out, "# EOF")?;
+        }
+        Ok(())
+    }
+
+    /// HELP and TYPE lines of a metric family (and its UNIT with OpenMetrics), a counter family
+    /// is named after its _total samples in Prometheus but without the suffix in OpenMetrics
+    fn write_metric_family(
+        out: &mut dyn Write,
+        openmetrics: bool,
+        name: &str,
+        kind: &str,
+        unit: &str,
+        help: &str
+    ) -> io::Result<()> {
+        let name = if kind == "counter" && !openmetrics { format!("{}_total", name) } else { name.to_string() };
+        writeln!(out, "# HELP {} {}", name, help)?;
+        writeln!(out, "# TYPE {} {}", name, kind)?;
+        if openmetrics && !unit.is_empty() {
+            writeln!(out, "# UNIT {} {}", name, unit)?;
+        }
+        Ok(())
+    }
+
+    /// Summary stats and the latency distribution as GitHub-flavored Markdown tables, from the same summary as the json report
+    fn print_markdown_report(&self, out: &mut dyn Write) -> io::Result<()> {
+        let summary = self.json_summary();
+        let unit = self.config.latency_unit;
+        let latency = |ms: f64| format!("{} {}", unit.format(ms), unit.label());
+
+        let mut stats = vec![
+            ("URL", format!("`{}`", summary.url)),
+            ("Concurrency", summary.concurrency.to_string()),
+            ("Duration", format!("{:.2} s", summary.duration_secs)),
+            ("Completed requests", summary.completed_requests.to_string()),
+            ("Failed requests", summary.failed_requests.to_string()),
+            ("Non-2xx responses", summary.non_2xx_responses.to_string()),
+            ("Requests/sec", format!("{:.3}", summary.requests_per_sec)),
+            ("Transfer/sec", format!("{:.3} KB", summary.total_transfer_per_sec_kb)),
+        ];
+        if let Some(started) = &summary.started_at {
+            stats.insert(0, ("Started", started.clone()));
+        }
+        if let Some(name) = &summary.name {
+            stats.insert(0, ("Name", name.clone()));
+        }
+        if let Some(stats_ms) = &summary.latency_ms {
+            stats.push(("Latency min", latency(stats_ms.min)));
+            stats.push(("Latency avg", latency(stats_ms.avg)));
+            stats.push(("Latency max", latency(stats_ms.max)));
+            stats.push(("Latency stdev", latency(stats_ms.stdev)));
+        }
+        let rows: Vec<[String; 2]> = stats
+            .into_iter()
+            .map(|(name, value)| [name.to_string(), value])
+            .collect();
+        writeln!(out, "### Summary\n")?;
+        Self::write_markdown_table(out, ["Metric", "Value"], &rows)?;
+
+        if !summary.percentiles_ms.is_empty() {
+            // the summary map is keyed by name, keep the --percentiles order instead
+            let rows: Vec<[String; 2]> = self.config.percentiles
+                .iter()
+                .filter_map(|p| summary.percentiles_ms.get(&format!("p{}", p)).map(|&ms| [format!("{}%", p), latency(ms)]))
+                .collect();
+            writeln!(out, "\n### Latency Distribution\n")?;
+            Self::write_markdown_table(out, ["Percentile", "Latency"], &rows)?;
+            if !summary.reliable_percentiles {
+                writeln!(
+                    out,
+                    "\n_Only {} samples, fewer than the {} needed for reliable percentiles._",
+                    summary.latency_samples,
+                    self.config.min_samples
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    // pads the cells so the table is readable as plain text too, the value column is right aligned
+    fn write_markdown_table(out: &mut dyn Write, headers: [&str; 2], rows: &[[String; 2]]) -> io::Result<()> {
+        let escape = |cell: &str| cell.replace('|', "\\|");
+        let mut widths = headers.map(|header| header.len().max(3));
+        for row in rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(escape(cell).chars().count());
+            }
+        }
+        writeln!(out, "| {:<w0$} | {:>w1$} |", headers[0], headers[1], w0 = widths[0], w1 = widths[1])?;
+        writeln!(out, "|:{}|{}:|", "-".repeat(widths[0] + 1), "-".repeat(widths[1] + 1))?;
+        for row in rows {
+            writeln!(out, "| {:<w0$} | {:>w1$} |", escape(&row[0]), escape(&row[1]), w0 = widths[0], w1 = widths[1])?;
+        }
+        Ok(())
+    }
+
+    // label values are quoted, backslashes, quotes and newlines need escaping
+    fn escape_label(value: &str) -> String {
+        value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+    }
+
+    // isahc error kinds (ConnectionFailed) as label values (connection_failed)
+    fn snake_case(name: &str) -> String {
+        let mut snake = String::with_capacity(name.len() + 4);
+        for (i, c) in name.chars().enumerate() {
+            if c.is_ascii_uppercase() {
+                if i > 0 {
+                    snake.push('_');
+                }
+                snake.push(c.to_ascii_lowercase());
+            } else {
+                snake.push(c);
+            }
+        }
+        snake
+    }
+
+    fn json_summary(&self) -> ReportSummary {
+        let warnings = self.warnings();
+        let report = self.report.lock().unwrap();
+
+        let duration = report.elapsed().as_secs_f64();
+
+        let latency_ms = report.latency_stats();
+        let mut percentiles_ms = BTreeMap::new();
+        if latency_ms.is_some() {
+            for &p in &self.config.percentiles {
+                percentiles_ms.insert(format!("p{}", p), report.latency_percentile(p));
+            }
+        }
+        let mut corrected_percentiles_ms = BTreeMap::new();
+        if !report.corrected_latencies.is_empty() {
+            for &p in &self.config.percentiles {
+                corrected_percentiles_ms.insert(
+                    format!("p{}", p),
+                    Report::histogram_percentile(&report.corrected_latencies, p)
+                );
+            }
+        }
+
+        let mut summary = ReportSummary {
+            name: report.name.clone(),
+            started_at: report.started_rfc3339(),
+            url: self.config.url.clone(),
+            sampled_urls: self.config.sampled_urls().map(<[String]>::to_vec),
+            host: report.host.clone(),
+            port: report.port,
+            server_software: report.server_software.clone(),
+            concurrency: report.concurrency,
+            connections: report.connections,
+            completed_requests: report.completed_requests,
+            failed_requests: report.failed_requests,
+            timeouts: report.timeouts,
+            connection_resets: report.connection_resets,
+            retries_total: report.retries_total,
+            errors_by_kind: report.errors_by_kind.clone(),
+            non_2xx_responses: report.non_2xx_responses,
+            informational_1xx: report.informational_1xx,
+            redirect_3xx: report.redirect_3xx,
+            client_error_4xx: report.client_error_4xx,
+            server_error_5xx: report.server_error_5xx,
+            status_counts: report.status_counts.clone(),
+            protocol_counts: report.protocol_counts.clone(),
+            redirects_followed: report.follow_redirects,
+            blocked_redirects: report.blocked_redirects,
+            graphql_errors: report.graphql_errors,
+            validation_failures: report.validation_failures,
+            seed: report.seed,
+            duration_secs: duration,
+            requests_per_sec: report.per_sec(report.completed_requests as f64),
+            total_html_read_kb: report.total_html_read,
+            total_wire_body_read_kb: report.total_wire_body_read,
+            transfer_per_sec_kb: report.per_sec(report.total_html_read),
+            total_transferred_kb: report.total_transferred(),
+            total_transfer_per_sec_kb: report.per_sec(report.total_transferred()),
+            latency_ms,
+            percentiles_ms,
+            reliable_percentiles: report.latency_count() >= self.config.min_samples,
+            latency_samples: report.latency_count(),
+            corrected_percentiles_ms,
+            connect_ms: Report::histogram_stats(&report.connect_times),
+            ttfb_ms: Report::histogram_stats(&report.ttfb_times),
+            total_ms: Report::histogram_stats(&report.total_times),
+            continue_ms: Report::histogram_stats(&report.continue_times),
+            upload_ms: Report::histogram_stats(&report.upload_times),
+            stages: self.stage_reports
+                .iter()
+                .map(|(stage, stage_report)| StageSummary {
+                    stage: stage.spec.clone(),
+                    concurrency: stage.concurrency,
+                    duration_secs: stage_report.elapsed().as_secs_f64(),
+                    completed_requests: stage_report.completed_requests,
+                    failed_requests: stage_report.failed_requests,
+                    requests_per_sec: stage_report.per_sec(stage_report.completed_requests as f64),
+                    latency_ms: stage_report.latency_stats(),
+                    percentiles_ms: if stage_report.latency_count() > 0 {
+                        self.config.percentiles
+                            .iter()
+                            .map(|&p| (format!("p{}", p), stage_report.latency_percentile(p)))
+                            .collect()
+                    } else {
+                        BTreeMap::new()
+                    },
+                })
+                .collect(),
+            warnings,
+            cut_short: report.cut_short,
+            fingerprint: String::new(),
+        };
+        // hashed from the serialized metrics, --verify recomputes it the same way from the file
+        summary.fingerprint = ReportSummary::fingerprint(&serde_json::to_value(&summary).unwrap_or_default());
+        summary
+    }
+
+    /// Write the human readable report (full or summarized) into the given sink
+    fn write_human_report(&self, out: &mut dyn Write) -> io::Result<()> {
+        if self.config.summarize {
+            self.print_summarized_report(out)?;
+        } else {
+            self.print_full_report(out)?;
+        }
+        if let Some(cold_report) = &self.cold_report {
+            writeln!(out)?;
+            self.print_cache_comparison(out, cold_report)?;
+        }
+        if !self.stage_reports.is_empty() {
+            writeln!(out)?;
+            self.print_stages(out)?;
+        }
+        Ok(())
+    }
+
+    /// Print a row per --stage, to see where the latency starts to climb with the load
+    fn print_stages(&self, out: &mut dyn Write) -> io::Result<()> {
+        let unit = self.config.latency_unit;
+        writeln!(out, "Stages:")?;
+        writeln!(
+            out,
+            " {:<16} {:>11} {:>10} {:>8} {:>10} {:>12} {:>12} {:>12}",
+            "Stage",
+            "Concurrency",
+            "Requests",
+            "Errors",
+            "Req/sec",
+            format!("Avg ({})", unit.label()),
+            format!("p50 ({})", unit.label()),
+            format!("p99 ({})", unit.label())
+        )?;
+        for (stage, report) in &self.stage_reports {
+            let latency = |value: f64| if report.latency_count() > 0 { unit.format(value) } else { "-".to_string() };
+            writeln!(
+                out,
+                " {:<16} {:>11} {:>10} {:>8} {:>10.2} {:>12} {:>12} {:>12}",
+                stage.spec,
+                stage.concurrency,
+                report.completed_requests,
+                report.failed_requests,
+                report.per_sec(report.completed_requests as f64),
+                latency(report.latency_stats().map_or(0.0, |stats| stats.avg)),
+                latency(report.latency_percentile(50.0)),
+                latency(report.latency_percentile(99.0))
+            )?;
+        }
+        Ok(())
     }
 
-    /// Check if the URL is reachable before running tests
-    fn is_url_reachable(&self, url: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let parsed_url = Url::parse(url).map_err(|e| format!("Invalid URL: {}", e))?;
-        let hostname = parsed_url
-            .host_str()
-            .ok_or_else(|| "URL does not have a valid hostname".to_string())?;
-        let port = parsed_url.port_or_known_default().unwrap_or(80); // HTTP port 80 if none sepcified
-
-        // set the hostname and port in report
-        let mut report = self.report.lock().unwrap();
-        report.host = hostname.to_string();
-        report.port = port;
-
-        let address = format!("{}:{}", hostname, port);
-        match TcpStream::connect(address) {
-            Ok(_) => {
-                if self.config.test_type == TestType::RequestCount {
-                    println!("Sending {} request(s) to {}", self.config.requests, self.config.url);
-                } else if self.config.test_type == TestType::Duration {
-                    println!(
-                        "Running {}s test on {}",
-                        self.config.duration.as_secs(),
-                        self.config.url
-                    );
-                } else {
-                    println!(
-                        "Sending {} request(s) to {} in {}s",
-                        self.config.requests,
-                        self.config.url,
-                        self.config.duration.as_secs()
-                    );
-                }
-                println!(
-                    "using {} thread(s) and {} connection(s)\nPlease be patient..",
-                    self.config.threads,
-                    self.config.concurrency
-                );
-                Ok(())
-            }
-            Err(e) => Err(format!("Failed to connect: {}", e).into()),
+    /// Print cold vs warm pass percentiles side by side
+    fn print_cache_comparison(&self, out: &mut dyn Write, cold_report: &Report) -> io::Result<()> {
+        let warm = self.report.lock().unwrap();
+        if cold_report.latency_count() == 0 || warm.latency_count() == 0 {
+            return writeln!(out, "Cache Comparison: no successful requests to compare");
         }
+
+        let (cold_p50, cold_p99) = (cold_report.latency_percentile(50.0), cold_report.latency_percentile(99.0));
+        let (warm_p50, warm_p99) = (warm.latency_percentile(50.0), warm.latency_percentile(99.0));
+        let speedup = |cold: f64, warm: f64| if warm > 0.0 { cold / warm } else { 0.0 };
+
+        let unit = self.config.latency_unit;
+        writeln!(out, "Cache Comparison:")?;
+        writeln!(
+            out,
+            " {:<10} {:<12} {:<12}",
+            "Pass",
+            format!("p50 ({})", unit.label()),
+            format!("p99 ({})", unit.label())
+        )?;
+        writeln!(out, " {:<10} {:<12} {:<12}", "Cold", unit.format(cold_p50), unit.format(cold_p99))?;
+        writeln!(out, " {:<10} {:<12} {:<12}", "Warm", unit.format(warm_p50), unit.format(warm_p99))?;
+        writeln!(
+            out,
+            " {:<10} {:<12} {:<12}",
+            "Speedup",
+            format!("{:.2}x", speedup(cold_p50, warm_p50)),
+            format!("{:.2}x", speedup(cold_p99, warm_p99))
+        )
     }
 
-    /*---------= Everything related to printing =----------*/
-    fn print_summarized_report(&self) {
-        print!("\n\n");
+    fn print_summarized_report(&self, out: &mut dyn Write) -> io::Result<()> {
+        write!(out, "\n\n")?;
 
-        let mut report = self.report.lock().unwrap();
+        let report = self.report.lock().unwrap();
 
-        Self::print_report_details_summary(&report);
+        if let Some(name) = &report.name {
+            writeln!(out, "Run: {}", name)?;
+        }
+        if let Some(started) = report.started_rfc3339() {
+            writeln!(out, "Started at {}", started)?;
+        }
+        self.print_cut_short(out, &report)?;
+        Self::print_report_details_summary(out, &report)?;
+        Self::print_error_breakdown(out, &report)?;
+        Self::print_connection_balance(out, &report)?;
 
-        // convert latencies in ms
-        report.latencies.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        if report.latency_count() == 0 {
+            writeln!(out, "Latency Stats: no successful requests")?;
+        } else {
+            let unit = self.config.latency_unit;
+            Self::print_request_timings_summary(out, &report, unit)?;
+            Self::print_queue_times(out, &report.queue_times, unit)?;
+            Self::print_latency_distribution(
+                out,
+                &report,
+                &self.config.percentiles,
+                self.config.min_samples,
+                self.config.sla,
+                unit
+            )?;
+            Self::print_corrected_distribution(out, &report, &self.config.percentiles, unit)?;
+        }
+        Self::print_report_throughput_summary(out, &report)?;
+        Self::print_throughput_percentiles(out, &report.rps_samples)
+    }
 
-        Self::print_request_timings_summary(&report.latencies);
-        Self::print_latency_distribution(&report.latencies);
-        Self::print_report_throughput_summary(&report);
+    /// Tell that --max-duration stopped the test and how much of it was done
+    fn print_cut_short(&self, out: &mut dyn Write, report: &Report) -> io::Result<()> {
+        let Some(max_duration) = self.config.max_duration.filter(|_| report.cut_short) else {
+            return Ok(());
+        };
+        writeln!(
+            out,
+            "Cut short by --max-duration {:?}: {} of {} requests finished\n",
+            max_duration,
+            report.completed_requests + report.failed_requests,
+            self.config.requests
+        )
     }
 
     /// Prints Throughput like req/sec and data_transfer/sec
-    fn print_report_throughput_summary(report: &Report) {
-        let duration = report.duration.as_secs_f64();
-        println!(
+    fn print_report_throughput_summary(out: &mut dyn Write, report: &Report) -> io::Result<()> {
+        writeln!(
+            out,
             "{:<20} {:>7.3}",
             "Request(s) per sec:",
-            (report.completed_requests as f64) / duration
-        );
-        println!(
+            report.per_sec(report.completed_requests as f64)
+        )?;
+        writeln!(
+            out,
             "{:<20} {:>7.3} KB (html)",
             "Transfer per sec:",
-            (report.total_html_read as f64) / duration
-        );
+            report.per_sec(report.total_html_read)
+        )?;
+        writeln!(
+            out,
+            "{:<20} {:>7.3} KB (html + headers)",
+            "",
+            report.per_sec(report.total_transferred())
+        )
     }
 
-    fn print_report_details_summary(report: &Report) {
-        println!(
-            "Sent {} requests in {:.2}s, {:.3}KB read (html)",
+    fn print_report_details_summary(out: &mut dyn Write, report: &Report) -> io::Result<()> {
+        writeln!(
+            out,
+            "Sent {} requests in {:.2}s, {:.3}KB read (html), {:.3}KB total transferred",
             report.completed_requests,
-            report.duration.as_secs_f64(),
-            report.total_html_read
-        );
+            report.elapsed().as_secs_f64(),
+            report.total_html_read,
+            report.total_transferred()
+        )?;
+        if let Some(expiry) = &report.cert_expiry {
+            writeln!(out, "Certificate expires on {}", expiry)?;
+        }
         if report.failed_requests > 0 {
-            println!(
-                "Failed Requests: {}, out of which timeouts {}",
+            writeln!(
+                out,
+                "Failed Requests: {}, out of which timeouts {}, connection resets {}",
                 report.failed_requests,
-                report.timeouts
-            );
+                report.timeouts,
+                report.connection_resets
+            )?;
+        }
+        if report.retries_total > 0 {
+            writeln!(out, "Retries: {}", report.retries_total)?;
+        }
+        for (host, count) in &report.dns_failures {
+            writeln!(out, "DNS resolution failures: {} for {}", count, host)?;
+        }
+        if let Some(deadline) = report.adaptive_timeout {
+            writeln!(
+                out,
+                "Adaptive timeouts: {} (deadline {:.2}ms)",
+                report.adaptive_timeouts,
+                deadline.as_secs_f64() * 1000.0
+            )?;
+        }
+        if report.non_2xx_responses > 0 {
+            writeln!(
+                out,
+                "Non 2.x.x responses: {} ({}, redirects {})",
+                report.non_2xx_responses,
+                Self::status_classes(report)
+                    .iter()
+                    .map(|(class, _, count)| format!("{} {}", class, count))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                Self::redirect_handling(report, "not followed")
+            )?;
+        }
+        if report.validation_failures > 0 {
+            writeln!(out, "Validation failures: {} (unexpected status or body)", report.validation_failures)?;
+        }
+        if report.graphql_errors > 0 {
+            writeln!(
+                out,
+                "GraphQL errors: {} ({:.2}% of completed)",
+                report.graphql_errors,
+                report.graphql_error_rate()
+            )?;
         }
+        Ok(())
     }
 
     /// Print request timings for summarized report
-    fn print_request_timings_summary(latencies_ms: &Vec<f64>) {
-        let mean = latencies_ms.iter().sum::<f64>() / (latencies_ms.len() as f64); // calculate mean
-        let variance: f64 = // calculate variance
-            latencies_ms
-                .iter()
-                .map(|&value| (value - mean).powi(2))
-                .sum::<f64>() / (latencies_ms.len() as f64);
-        let stdev = variance.sqrt(); // calculate standard deviation
-        let min = latencies_ms.iter().cloned().fold(f64::INFINITY, f64::min); // minimum latency
-        let max = latencies_ms.iter().cloned().fold(f64::NEG_INFINITY, f64::max); // maximum latency
-
-        println!("Latnecy Stats:");
-        println!(" {:<10} {:<10} {:<10} {:<10}", "Avg", "Min", "Max", "Stdev");
-        println!(
+    fn print_request_timings_summary(
+        out: &mut dyn Write,
+        report: &Report,
+        unit: LatencyUnit
+    ) -> io::Result<()> {
+        let Some(stats) = report.latency_stats() else {
+            return Ok(()); // no successful requests
+        };
+
+        writeln!(out, "Latnecy Stats:")?;
+        writeln!(out, " {:<10} {:<10} {:<10} {:<10}", "Avg", "Min", "Max", "Stdev")?;
+        writeln!(
+            out,
             " {:<10} {:<10} {:<10} {:<10}",
-            Self::format_latency(mean),
-            Self::format_latency(min),
-            Self::format_latency(max),
-            Self::format_latency(stdev)
-        );
+            Self::format_latency(stats.avg, unit),
+            Self::format_latency(stats.min, unit),
+            Self::format_latency(stats.max, unit),
+            Self::format_latency(stats.stdev, unit)
+        )
     }
 
-    // convert into seconds if the value is greater than 1000ms
-    fn format_latency(value: f64) -> String {
-        if value > 1000.0 {
+    // in auto mode convert into seconds if the value is greater than 1000ms
+    fn format_latency(value: f64, unit: LatencyUnit) -> String {
+        if unit != LatencyUnit::Auto {
+            format!("{}{}", unit.format(value), unit.label())
+        } else if value > 1000.0 {
             format!("{:.2}s", value / 1000.0) // convert to seconds
         } else {
             format!("{:.2}ms", value) // keep in milliseconds
         }
     }
 
-    fn print_full_report(&self) {
-        let mut report = self.report.lock().unwrap();
+    fn print_full_report(&self, out: &mut dyn Write) -> io::Result<()> {
+        let report = self.report.lock().unwrap();
+
+        write!(out, "\n\n")?;
 
-        print!("\n\n");
+        // Options the test ran with, so a saved report tells what produced it
+        self.print_settings(out, false)?;
+        writeln!(out)?;
+
+        self.print_cut_short(out, &report)?;
 
         // Report Details
-        Self::print_report_details_full(&report, FIELD_WIDTH);
-        println!();
+        Self::print_report_details_full(out, &report, FIELD_WIDTH)?;
+        writeln!(out)?;
+
+        // Errors
+        if !report.errors_by_kind.is_empty() {
+            Self::print_error_breakdown(out, &report)?;
+            writeln!(out)?;
+        }
+
+        // Status codes and protocols
+        if !report.status_counts.is_empty() {
+            Self::print_status_codes(out, &report)?;
+            writeln!(out)?;
+            Self::print_protocols(out, &report)?;
+            writeln!(out)?;
+        }
+
+        // Achieved mix of the --script requests
+        if !report.script_counts.is_empty() {
+            Self::print_request_mix(out, &report, &self.config.script)?;
+            writeln!(out)?;
+        }
+
+        // Connection balancing
+        if !report.requests_per_connection.is_empty() {
+            Self::print_connection_balance(out, &report)?;
+            writeln!(out)?;
+        }
+
+        // without a single latency there are no timings, percentiles or histogram to show
+        if report.latency_count() == 0 {
+            writeln!(out, "Time Taken for Requests: no successful requests")?;
+            writeln!(out)?;
+            return Self::print_throughput_percentiles(out, &report.rps_samples);
+        }
 
-        // convert latencies in ms
-        report.latencies.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let unit = self.config.latency_unit;
 
         // Request Timings
-        Self::print_request_timings_full(&report.latencies);
+        Self::print_request_timings_full(out, &report, unit)?;
+        Self::print_queue_times(out, &report.queue_times, unit)?;
+        writeln!(out)?;
+        Self::print_request_phases(out, &report, unit)?;
+        if !report.server_timings.is_empty() {
+            writeln!(out)?;
+            Self::print_server_timings(out, &report, unit)?;
+        }
 
-        println!();
+        writeln!(out)?;
         // Distribution
-        Self::print_latency_distribution(&report.latencies);
-        println!();
+        Self::print_latency_distribution(
+            out,
+            &report,
+            &self.config.percentiles,
+            self.config.min_samples,
+            self.config.sla,
+            unit
+        )?;
+        if !report.corrected_latencies.is_empty() {
+            writeln!(out)?;
+            Self::print_corrected_distribution(out, &report, &self.config.percentiles, unit)?;
+        }
+        writeln!(out)?;
+
+        // Throughput
+        Self::print_throughput_percentiles(out, &report.rps_samples)?;
+        writeln!(out)?;
 
         // Histogram
-        Self::print_latency_histogram(&report.latencies);
+        Self::print_latency_histogram(out, &report, self.config.buckets, self.config.hist_max, self.config.sla, unit)
     }
 
     /// Print details for full report
-    fn print_report_details_full(report: &Report, field_width: usize) {
-        println!("{:<field_width$}{}", "Hostname:", report.host, field_width = field_width);
-        println!("{:<field_width$}{}", "Port:", report.port, field_width = field_width);
-        println!(
-            "{:<field_width$}{}\n",
+    fn print_report_details_full(
+        out: &mut dyn Write,
+        report: &Report,
+        field_width: usize
+    ) -> io::Result<()> {
+        if let Some(started) = report.started_rfc3339() {
+            writeln!(out, "{:<field_width$}{}", "Started:", started, field_width = field_width)?;
+        }
+        writeln!(out, "{:<field_width$}{}", "Hostname:", report.host, field_width = field_width)?;
+        writeln!(out, "{:<field_width$}{}", "Port:", report.port, field_width = field_width)?;
+        if let Some(expiry) = &report.cert_expiry {
+            writeln!(
+                out,
+                "{:<field_width$}{}",
+                "Certificate Expiry:",
+                expiry,
+                field_width = field_width
+            )?;
+        }
+        writeln!(
+            out,
+            "{:<field_width$}{}",
             "Server Software:",
             report.server_software,
             field_width = field_width
-        );
+        )?;
+        writeln!(
+            out,
+            "{:<field_width$}{}",
+            "Concurrency Level:",
+            report.concurrency,
+            field_width = field_width
+        )?;
+        writeln!(
+            out,
+            "{:<field_width$}{}",
+            "Connections:",
+            report.connections.map_or("unbounded".to_string(), |connections| connections.to_string()),
+            field_width = field_width
+        )?;
+        writeln!(out, "{:<field_width$}{}\n", "Seed:", report.seed, field_width = field_width)?;
 
-        println!(
+        writeln!(
+            out,
             "{:<field_width$}{}",
             "Completed Requests:",
             report.completed_requests,
             field_width = field_width
-        );
+        )?;
         if report.failed_requests > 0 {
-            println!(
+            writeln!(
+                out,
                 "{:<field_width$}{} (including timeouts)",
                 "Failed Requests:",
                 report.failed_requests,
                 field_width = field_width
-            );
-            println!("{:<field_width$}{}", "Timeouts:", report.timeouts, field_width = field_width);
+            )?;
+            writeln!(
+                out,
+                "{:<field_width$}{}",
+                "Timeouts:",
+                report.timeouts,
+                field_width = field_width
+            )?;
+            writeln!(
+                out,
+                "{:<field_width$}{}",
+                "Connection Resets:",
+                report.connection_resets,
+                field_width = field_width
+            )?;
+        }
+        if report.retries_total > 0 {
+            writeln!(
+                out,
+                "{:<field_width$}{}",
+                "Retries:",
+                report.retries_total,
+                field_width = field_width
+            )?;
+        }
+        for (host, count) in &report.dns_failures {
+            writeln!(
+                out,
+                "{:<field_width$}{} ({})",
+                "DNS Failures:",
+                count,
+                host,
+                field_width = field_width
+            )?;
+        }
+        if let Some(deadline) = report.adaptive_timeout {
+            writeln!(
+                out,
+                "{:<field_width$}{} (deadline {:.2}ms)",
+                "Adaptive Timeouts:",
+                report.adaptive_timeouts,
+                deadline.as_secs_f64() * 1000.0,
+                field_width = field_width
+            )?;
+        }
+        if report.validation_failures > 0 {
+            writeln!(
+                out,
+                "{:<field_width$}{} (unexpected status or body)",
+                "Validation Failures:",
+                report.validation_failures,
+                field_width = field_width
+            )?;
+        }
+        if report.graphql_errors > 0 {
+            writeln!(
+                out,
+                "{:<field_width$}{} ({:.2}% of completed)",
+                "GraphQL Errors:",
+                report.graphql_errors,
+                report.graphql_error_rate(),
+                field_width = field_width
+            )?;
         }
         if report.non_2xx_responses > 0 {
-            println!(
+            writeln!(
+                out,
                 "{:<field_width$}{}",
                 "Non 2.x.x Responses:",
                 report.non_2xx_responses,
                 field_width = field_width
-            );
+            )?;
+            for (class, name, count) in Self::status_classes(report) {
+                writeln!(
+                    out,
+                    "{:<field_width$}{}",
+                    format!(" {} {}:", class, name),
+                    count,
+                    field_width = field_width
+                )?;
+            }
         }
-        println!(
+        writeln!(
+            out,
+            "{:<field_width$}{}",
+            "Redirects:",
+            Self::redirect_handling(report, "not followed (3xx count as non 2.x.x)"),
+            field_width = field_width
+        )?;
+        writeln!(
+            out,
             "{:<field_width$}{:.2}",
             "Requests/sec:",
-            (report.completed_requests as f64) / report.duration.as_secs_f64(),
+            report.per_sec(report.completed_requests as f64),
             field_width = field_width
-        );
-        println!(
+        )?;
+        writeln!(
+            out,
             "{:<field_width$}{:.4} KB",
             "Total HTML Read:",
             report.total_html_read,
             field_width = field_width
-        );
-        println!(
+        )?;
+        if report.has_compressed_bodies() {
+            writeln!(
+                out,
+                "{:<field_width$}{:.4} KB (compressed html)",
+                "Total Wire Body Read:",
+                report.total_wire_body_read,
+                field_width = field_width
+            )?;
+        }
+        writeln!(
+            out,
+            "{:<field_width$}{:.4} KB (html + headers)",
+            "Total Transferred:",
+            report.total_transferred(),
+            field_width = field_width
+        )?;
+        writeln!(
+            out,
+            "{:<field_width$}{:.4} KB",
+            "Transfer/sec:",
+            report.per_sec(report.total_transferred()),
+            field_width = field_width
+        )?;
+        writeln!(
+            out,
             "{:<field_width$}{:.2}s",
             "Total Time Taken:",
-            report.duration.as_secs_f64(),
+            report.elapsed().as_secs_f64(),
             field_width = field_width
-        );
+        )
     }
 
     /// Print request timings for full report
-    fn print_request_timings_full(latencies_ms: &Vec<f64>) {
-        // Calculate min, max, and average
-        let min = latencies_ms.first().cloned().unwrap_or(0.0);
-        let max = latencies_ms.last().cloned().unwrap_or(0.0);
-        let avg = latencies_ms.iter().copied().sum::<f64>() / (latencies_ms.len() as f64);
+    fn print_request_timings_full(
+        out: &mut dyn Write,
+        report: &Report,
+        unit: LatencyUnit
+    ) -> io::Result<()> {
+        let Some(stats) = report.latency_stats() else {
+            return Ok(()); // no successful requests
+        };
 
         // Print in a single row with formatting
-        println!("Time Taken for Requests:");
-        println!(" {:<12} {:<12} {:<12}", "Min (ms)", "Avg (ms)", "Max (ms)");
-        println!(" {:<12.2} {:<12.2} {:<12.2}", min, avg, max);
+        writeln!(out, "Time Taken for Requests:")?;
+        writeln!(
+            out,
+            " {:<12} {:<12} {:<12} {:<12}",
+            format!("Min ({})", unit.label()),
+            format!("Avg ({})", unit.label()),
+            format!("Max ({})", unit.label()),
+            format!("Stdev ({})", unit.label())
+        )?;
+        writeln!(
+            out,
+            " {:<12} {:<12} {:<12} {:<12}",
+            unit.format(stats.min),
+            unit.format(stats.avg),
+            unit.format(stats.max),
+            unit.format(stats.stdev)
+        )
+    }
+
+    /// Print the distribution of the connect, time to first byte and total times (from the request metrics)
+    /// to tell a slow to connect server from a slow to respond one
+    fn print_request_phases(out: &mut dyn Write, report: &Report, unit: LatencyUnit) -> io::Result<()> {
+        if report.total_times.is_empty() {
+            return Ok(()); // metrics were not available
+        }
+
+        writeln!(out, "Request Phases:")?;
+        writeln!(
+            out,
+            " {:<8} {:<12} {:<12} {:<12} {:<12} {:<12}",
+            "",
+            format!("Min ({})", unit.label()),
+            format!("Avg ({})", unit.label()),
+            format!("p50 ({})", unit.label()),
+            format!("p99 ({})", unit.label()),
+            format!("Max ({})", unit.label())
+        )?;
+        let phases = [
+            ("Connect", &report.connect_times),
+            ("TTFB", &report.ttfb_times),
+            ("Total", &report.total_times),
+            ("Continue", &report.continue_times),
+            ("Upload", &report.upload_times),
+        ];
+        for (name, histogram) in phases {
+            let Some(stats) = Report::histogram_stats(histogram) else {
+                continue;
+            };
+            writeln!(
+                out,
+                " {:<8} {:<12} {:<12} {:<12} {:<12} {:<12}",
+                name,
+                unit.format(stats.min),
+                unit.format(stats.avg),
+                unit.format(Report::histogram_percentile(histogram, 50.0)),
+                unit.format(Report::histogram_percentile(histogram, 99.0)),
+                unit.format(stats.max)
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Print the average backend duration of each Server-Timing metric next to the measured latency
+    fn print_server_timings(out: &mut dyn Write, report: &Report, unit: LatencyUnit) -> io::Result<()> {
+        writeln!(out, "Server Timing:")?;
+        writeln!(out, " {:<24} {:<12} {:>10}", "", format!("Avg ({})", unit.label()), "Responses")?;
+        for (name, timing) in &report.server_timings {
+            let avg = timing.total_ms / (timing.samples as f64);
+            writeln!(out, " {:<24} {:<12} {:>10}", name, unit.format(avg), timing.samples)?;
+        }
+        if let Some(stats) = report.latency_stats() {
+            writeln!(out, " {:<24} {:<12} {:>10}", "(client latency)", unit.format(stats.avg), report.latency_count())?;
+        }
+        Ok(())
+    }
+
+    /// Print the failed requests per error kind
+    fn print_error_breakdown(out: &mut dyn Write, report: &Report) -> io::Result<()> {
+        if report.errors_by_kind.is_empty() {
+            return Ok(()); // every request completed
+        }
+
+        writeln!(out, "Errors by Kind:")?;
+        for (kind, count) in &report.errors_by_kind {
+            let share = ((*count as f64) / (report.failed_requests as f64)) * 100.0;
+            writeln!(out, " {:<24} {:>10} {:>6.1}%", kind, count, share)?;
+        }
+        Ok(())
+    }
+
+    /// Whether the redirects were followed, with the ones --restrict-redirects blocked
+    fn redirect_handling(report: &Report, not_followed: &str) -> String {
+        match (report.follow_redirects, report.blocked_redirects) {
+            (false, _) => not_followed.to_string(),
+            (true, 0) => "followed".to_string(),
+            (true, blocked) => format!("followed, {} blocked by --restrict-redirects", blocked),
+        }
+    }
+
+    /// Non 2.x.x response classes that were seen as (class, name, count)
+    fn status_classes(report: &Report) -> Vec<(&'static str, &'static str, usize)> {
+        [
+            ("1xx", "Informational", report.informational_1xx),
+            ("3xx", "Redirects", report.redirect_3xx),
+            ("4xx", "Client Errors", report.client_error_4xx),
+            ("5xx", "Server Errors", report.server_error_5xx),
+        ]
+            .into_iter()
+            .filter(|&(_, _, count)| count > 0)
+            .collect()
+    }
+
+    /// Print the responses per status code (e.g 503 overload vs 404 wrong path)
+    fn print_status_codes(out: &mut dyn Write, report: &Report) -> io::Result<()> {
+        writeln!(out, "Status Codes:")?;
+        for (status, count) in &report.status_counts {
+            let share = ((*count as f64) / (report.completed_requests as f64)) * 100.0;
+            writeln!(out, " {:<24} {:>10} {:>6.1}%", status, count, share)?;
+        }
+        Ok(())
+    }
+
+    /// Print the requests sent per --script line next to the share its weight asked for
+    fn print_request_mix(out: &mut dyn Write, report: &Report, script: &[RequestSpec]) -> io::Result<()> {
+        let sent: usize = report.script_counts.values().sum();
+        let total_weight: usize = script.iter().map(|spec| spec.weight).sum();
+        writeln!(out, "Request Mix:")?;
+        for (index, spec) in script.iter().enumerate() {
+            let count = report.script_counts.get(&index).copied().unwrap_or(0);
+            writeln!(
+                out,
+                " {:<40} {:>10} {:>6.1}% (weight {:.1}%)",
+                format!("{} {}", spec.method, spec.url),
+                count,
+                ((count as f64) / (sent as f64)) * 100.0,
+                ((spec.weight as f64) / (total_weight as f64)) * 100.0
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Print the responses per negotiated HTTP version (e.g to verify ALPN picked h2)
+    fn print_protocols(out: &mut dyn Write, report: &Report) -> io::Result<()> {
+        writeln!(out, "Protocols:")?;
+        for (protocol, count) in &report.protocol_counts {
+            let share = ((*count as f64) / (report.completed_requests as f64)) * 100.0;
+            writeln!(out, " {:<24} {:>10} {:>6.1}%", protocol, count, share)?;
+        }
+        Ok(())
+    }
+
+    /// Print how evenly the requests were spread across the connections
+    fn print_connection_balance(out: &mut dyn Write, report: &Report) -> io::Result<()> {
+        let counts: Vec<f64> = report.requests_per_connection
+            .values()
+            .map(|&count| count as f64)
+            .collect();
+        if counts.is_empty() {
+            return Ok(()); // no connection info available
+        }
+
+        let mean = counts.iter().sum::<f64>() / (counts.len() as f64);
+        let stdev = (
+            counts
+                .iter()
+                .map(|&count| (count - mean).powi(2))
+                .sum::<f64>() / (counts.len() as f64)
+        ).sqrt();
+        let min = counts.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = counts.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+
+        writeln!(out, "Requests per Connection ({} connection(s)):", counts.len())?;
+        writeln!(out, " {:<10} {:<10} {:<10} {:<10}", "Avg", "Min", "Max", "Stdev")?;
+        writeln!(out, " {:<10.2} {:<10} {:<10} {:<10.2}", mean, min, max, stdev)
     }
 
-    fn print_latency_distribution(latencies_ms: &Vec<f64>) {
-        if latencies_ms.len() == 0 {
-            return; // no requests were sent
+    /// Print percentiles of the per-second throughput (reveals pauses hidden by the mean)
+    fn print_throughput_percentiles(out: &mut dyn Write, rps_samples: &[usize]) -> io::Result<()> {
+        if rps_samples.len() < MIN_THROUGHPUT_SAMPLES {
+            return writeln!(
+                out,
+                "Throughput Distribution: run too short (needs at least {}s)",
+                MIN_THROUGHPUT_SAMPLES
+            );
         }
 
-        // calculate percentile
-        let percentile = |p: f64| -> f64 {
-            let idx = ((p / 100.0) * (latencies_ms.len() as f64)) as usize;
-            latencies_ms[idx.min(latencies_ms.len() - 1)]
+        let mut samples: Vec<f64> = rps_samples
+            .iter()
+            .map(|&count| count as f64)
+            .collect();
+        samples.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        writeln!(out, "Throughput Distribution (req/s):")?;
+        writeln!(out, " {:<10} {:<10} {:<10}", "p5", "p50", "p95")?;
+        writeln!(
+            out,
+            " {:<10} {:<10} {:<10}",
+            Report::percentile(&samples, 5.0),
+            Report::percentile(&samples, 50.0),
+            Report::percentile(&samples, 95.0)
+        )
+    }
+
+    /// Print time requests spent queued inside the client before being sent
+    fn print_queue_times(
+        out: &mut dyn Write,
+        queue_times: &Histogram<u64>,
+        unit: LatencyUnit
+    ) -> io::Result<()> {
+        let Some(stats) = Report::histogram_stats(queue_times) else {
+            return Ok(()); // metrics were not available
         };
+        let (mean, p99) = (stats.avg, Report::histogram_percentile(queue_times, 99.0));
+
+        writeln!(out, "Client Queue Time:")?;
+        writeln!(
+            out,
+            " {:<12} {:<12}",
+            format!("Avg ({})", unit.label()),
+            format!("p99 ({})", unit.label())
+        )?;
+        writeln!(out, " {:<12} {:<12}", unit.format(mean), unit.format(p99))?;
+
+        // a queue time comparable to the latency means the connection pool is the bottleneck
+        if p99 >= QUEUE_SATURATION_MS {
+            writeln!(
+                out,
+                " Requests are waiting for free connections, the client pool is saturated (try lowering -c)"
+            )?;
+        }
+        Ok(())
+    }
+
+    fn print_latency_distribution(
+        out: &mut dyn Write,
+        report: &Report,
+        percentiles: &[f64],
+        min_samples: usize,
+        sla: Option<f64>,
+        unit: LatencyUnit
+    ) -> io::Result<()> {
+        let count = report.latency_count();
+        if count == 0 {
+            return Ok(()); // no requests were sent
+        }
 
-        // get the required percentiles
-        let p50 = percentile(50.0);
-        let p75 = percentile(75.0);
-        let p90 = percentile(90.0);
-        let p99 = percentile(99.0);
+        // percentiles of a handful of samples are meaningless, show the raw values instead
+        if count < min_samples {
+            writeln!(
+                out,
+                "Latency Distribution: insufficient samples for reliable percentiles ({} < {})",
+                count,
+                min_samples
+            )?;
+            let values: Vec<String> = report
+                .latency_counts()
+                .into_iter()
+                .flat_map(|(latency, count)| std::iter::repeat_n(unit.format(latency), count as usize))
+                .collect();
+            writeln!(out, " Latencies ({}): {}", unit.label(), values.join(", "))?;
+        } else {
+            writeln!(out, "Latency Distribution:")?;
+            for &p in percentiles {
+                let label = format!("{}%", p);
+                writeln!(out, " {:<7}{} {}", label, unit.format(report.latency_percentile(p)), unit.label())?;
+            }
+        }
+        match sla {
+            Some(sla) => Self::print_sla(out, report, sla, unit),
+            None => Ok(()),
+        }
+    }
 
-        // Print results
-        println!("Latency Distribution:");
-        println!(" 50%    {:.2} ms", p50);
-        println!(" 75%    {:.2} ms", p75);
-        println!(" 90%    {:.2} ms", p90);
-        println!(" 99%    {:.2} ms", p99);
+    /// Print the share of the requests slower than the --sla and whether the p99 is within it
+    fn print_sla(out: &mut dyn Write, report: &Report, sla: f64, unit: LatencyUnit) -> io::Result<()> {
+        let count = report.latency_count();
+        let above: u64 = report
+            .latency_counts()
+            .into_iter()
+            .filter(|&(latency, _)| latency > sla)
+            .map(|(_, count)| count)
+            .sum();
+        writeln!(
+            out,
+            " Above SLA ({} {}): {:.2}% ({} of {})",
+            unit.format(sla),
+            unit.label(),
+            ((above as f64) / (count as f64)) * 100.0,
+            above,
+            count
+        )?;
+        let p99 = report.latency_percentile(99.0);
+        writeln!(
+            out,
+            "SLA: {} (p99 {} {} {} {} {})",
+            if p99 <= sla { "PASS" } else { "FAIL" },
+            unit.format(p99),
+            unit.label(),
+            if p99 <= sla { "<=" } else { ">" },
+            unit.format(sla),
+            unit.label()
+        )
     }
 
-    /// For printing latency histogram
-    fn print_latency_histogram(latencies_ms: &Vec<f64>) {
-        if latencies_ms.is_empty() {
-            return; // no requests were sent
+    /// Print the percentiles measured from the actual vs the scheduled send time (with --correct-co),
+    /// a large gap means slow responses held back the following requests (coordinated omission)
+    fn print_corrected_distribution(
+        out: &mut dyn Write,
+        report: &Report,
+        percentiles: &[f64],
+        unit: LatencyUnit
+    ) -> io::Result<()> {
+        if report.corrected_latencies.is_empty() {
+            return Ok(()); // not correcting
+        }
+
+        writeln!(out, "Coordinated Omission:")?;
+        writeln!(
+            out,
+            " {:<7}{:<16} {:<16}",
+            "",
+            format!("Measured ({})", unit.label()),
+            format!("Corrected ({})", unit.label())
+        )?;
+        for &p in percentiles {
+            writeln!(
+                out,
+                " {:<7}{:<16} {:<16}",
+                format!("{}%", p),
+                unit.format(report.latency_percentile(p)),
+                unit.format(Report::histogram_percentile(&report.corrected_latencies, p))
+            )?;
         }
+        Ok(())
+    }
 
-        let max = latencies_ms.last().copied().unwrap_or(0.0);
-        let bucket_size = max / (BUCKET_COUNT as f64);
+    /// For printing latency histogram
+    fn print_latency_histogram(
+        out: &mut dyn Write,
+        report: &Report,
+        buckets: usize,
+        hist_max: Option<f64>,
+        sla: Option<f64>,
+        unit: LatencyUnit
+    ) -> io::Result<()> {
+        let Some(stats) = report.latency_stats() else {
+            return Ok(()); // no requests were sent
+        };
 
-        let mut histogram = vec![0; BUCKET_COUNT]; // using vector for better readability
-        for &latency in latencies_ms {
-            let bucket = (latency / bucket_size).min((BUCKET_COUNT - 1) as f64) as usize;
-            histogram[bucket] += 1;
+        // a clamped range keeps a single outlier from squashing every other request into the first bucket
+        let max = hist_max.map_or(stats.max, |hist_max| hist_max.min(stats.max));
+        let clamped = max < stats.max;
+        let bucket_size = max / (buckets as f64);
+
+        let mut histogram = vec![0; buckets];
+        for (latency, count) in report.latency_counts() {
+            let bucket = if bucket_size > 0.0 {
+                (latency / bucket_size).min((buckets - 1) as f64) as usize
+            } else {
+                0 // every latency is 0
+            };
+            histogram[bucket] += count;
         }
+        let fullest = histogram.iter().copied().max().unwrap_or(0).max(1);
+        // an SLA past the last bucket is only marked when that bucket holds the outliers
+        let sla_bucket = sla
+            .filter(|&sla| clamped || sla <= max)
+            .map(|sla| {
+                let bucket = if bucket_size > 0.0 { (sla / bucket_size).min((buckets - 1) as f64) as usize } else { 0 };
+                (bucket, sla)
+            });
 
-        println!("{:<15} {:<15} {:>10}", "Range (ms)", "Upper Bound", "Requests");
+        writeln!(out, "{:<15} {:<15} {:>10}", format!("Range ({})", unit.label()), "Upper Bound", "Requests")?;
 
         for (i, &count) in histogram.iter().enumerate() {
             let lower_bound = (i as f64) * bucket_size;
             let upper_bound = ((i as f64) + 1.0) * bucket_size;
-            println!("{:<15.2} {:<15.2} {:>10}", lower_bound, upper_bound, count);
+            let upper_bound = if clamped && i == buckets - 1 {
+                format!("{}+", unit.format(upper_bound)) // holds the outliers as well
+            } else {
+                unit.format(upper_bound)
+            };
+            let bar = "#".repeat(((count as usize) * HISTOGRAM_BAR_WIDTH).div_ceil(fullest as usize));
+            let line = format!("{:<15} {:<15} {:>10} {}", unit.format(lower_bound), upper_bound, count, bar);
+            if let Some((_, sla)) = sla_bucket.filter(|&(bucket, _)| bucket == i) {
+                writeln!(out, "{}  <- SLA {} {}", line.trim_end(), unit.format(sla), unit.label())?;
+            } else {
+                writeln!(out, "{}", line.trim_end())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::VerifyError;
+    use std::io::Read;
+
+    fn socket_address(url: &str) -> Option<String> {
+        Runner::socket_address(&Url::parse(url).unwrap(), &[])
+    }
+
+    fn runner(args: &[&str]) -> Runner {
+        let args: Vec<String> = std::iter::once(&"benchener").chain(args).map(|arg| arg.to_string()).collect();
+        Runner::new(Config::parse_args(&args).unwrap())
+    }
+
+    /// Answer every request on a local port with `response`, returns the url
+    fn serve(response: &'static [u8]) -> String {
+        serve_by(move |_| response)
+    }
+
+    // like serve, with the response picked from the request head
+    fn serve_by(respond: impl Fn(&[u8]) -> &'static [u8] + Send + Copy + 'static) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                std::thread::spawn(move || {
+                    let mut buf = [0; 4096];
+                    // requests carry no body, one response per end of headers (keep-alive)
+                    while let Ok(read @ 1..) = stream.read(&mut buf) {
+                        let mut head_start = 0;
+                        for end in (0..read.saturating_sub(3)).filter(|&i| &buf[i..i + 4] == b"\r\n\r\n") {
+                            if stream.write_all(respond(&buf[head_start..end])).is_err() {
+                                return;
+                            }
+                            head_start = end + 4;
+                        }
+                    }
+                });
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn socket_address_brackets_ipv6() {
+        assert_eq!(socket_address("http://[::1]:8080").as_deref(), Some("[::1]:8080"));
+        assert_eq!(socket_address("https://[2001:db8::1]/path").as_deref(), Some("[2001:db8::1]:443"));
+    }
+
+    #[test]
+    fn socket_address_follows_resolve() {
+        let resolve = [
+            ("example.com".to_string(), 443, "10.0.0.5".parse().unwrap()),
+            ("example.com".to_string(), 8080, "::1".parse().unwrap()),
+        ];
+        let address = |url: &str| Runner::socket_address(&Url::parse(url).unwrap(), &resolve);
+        assert_eq!(address("https://example.com/health").as_deref(), Some("10.0.0.5:443"));
+        assert_eq!(address("http://example.com:8080").as_deref(), Some("[::1]:8080"));
+        assert_eq!(address("http://example.com").as_deref(), Some("example.com:80")); // other port
+        assert_eq!(address("https://api.example.com").as_deref(), Some("api.example.com:443"));
+    }
+
+    #[test]
+    fn connect_tells_refused_from_unresolved() {
+        // a port that was just freed has nothing listening on it
+        let address = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+        let err = Runner::connect(&address, Duration::from_secs(1)).unwrap_err();
+        assert!(err.starts_with("connection refused by"), "{}", err);
+
+        let err = Runner::connect("no-such-host.invalid:80", Duration::from_secs(1)).unwrap_err();
+        assert!(err.starts_with("DNS resolution of"), "{}", err);
+    }
+
+    #[test]
+    fn server_timing_durations() {
+        assert_eq!(
+            Runner::parse_server_timing("db;dur=53, app;desc=\"Render\";dur=47.2, cache;desc=\"hit\""),
+            vec![("db".to_string(), 53.0), ("app".to_string(), 47.2)]
+        );
+        assert_eq!(Runner::parse_server_timing("total;DUR=\"1.5\""), vec![("total".to_string(), 1.5)]);
+        assert!(Runner::parse_server_timing("miss, ;dur=3, db;dur=slow").is_empty());
+    }
+
+    #[test]
+    fn socket_address_of_names_and_ipv4() {
+        assert_eq!(socket_address("http://127.0.0.1:8080").as_deref(), Some("127.0.0.1:8080"));
+        assert_eq!(socket_address("http://localhost").as_deref(), Some("localhost:80"));
+        assert_eq!(socket_address("https://example.com").as_deref(), Some("example.com:443"));
+        assert_eq!(socket_address("mailto:someone@example.com"), None);
+    }
+
+    #[test]
+    fn formats_rfc3339_in_utc() {
+        let at = |secs: u64| Report::format_rfc3339(UNIX_EPOCH + Duration::from_secs(secs));
+        assert_eq!(at(0), "1970-01-01T00:00:00Z");
+        assert_eq!(at(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(at(1_738_314_300), "2025-01-31T09:05:00Z");
+        assert_eq!(at(4_102_444_799), "2099-12-31T23:59:59Z");
+    }
+
+    #[test]
+    fn percentile_uses_the_nearest_rank() {
+        let values: Vec<f64> = (1..=100).map(f64::from).collect();
+        assert_eq!(Report::percentile(&values, 50.0), 50.0);
+        assert_eq!(Report::percentile(&values, 90.0), 90.0);
+        assert_eq!(Report::percentile(&values, 99.0), 99.0);
+        assert_eq!(Report::percentile(&values, 100.0), 100.0);
+        assert_eq!(Report::percentile(&values, 0.0), 1.0);
+    }
+
+    #[test]
+    fn fail_conditions_fail_the_run() {
+        let runner = runner(&["--fail-if", "p99>200ms", "--fail-if", "error_rate>1%", "http://localhost:8080"]);
+        {
+            let mut report = runner.report.lock().unwrap();
+            for _ in 0..99 {
+                report.record_latency(Duration::from_millis(50));
+            }
+            report.record_latency(Duration::from_millis(500));
+            report.completed_requests = 100;
+        }
+        assert_eq!(runner.check_fail_conditions(), Ok(())); // p99 is 50ms, nothing failed
+
+        runner.report.lock().unwrap().failed_requests = 5;
+        let err = runner.check_fail_conditions().unwrap_err();
+        assert!(err.starts_with("Failed: 1 condition(s) held"), "{}", err);
+        assert!(err.contains("error_rate>1% (was 4.76)"), "{}", err);
+    }
+
+    #[test]
+    fn latency_fail_conditions_need_a_successful_request() {
+        let runner = runner(&["--fail-if", "p99>200ms", "http://localhost:8080"]);
+        runner.report.lock().unwrap().failed_requests = 3;
+        assert!(runner.check_fail_conditions().unwrap_err().contains("p99>200ms (no successful requests)"));
+    }
+
+    #[test]
+    fn non_utf8_bodies_complete() {
+        let url = serve(b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\n\xff\xfe\xfd\xfc");
+        let report = runner(&["-q", "-n", "3", "-c", "1", &url]).run().unwrap();
+        assert_eq!((report.completed_requests, report.failed_requests, report.non_2xx_responses), (3, 0, 0));
+        assert_eq!(report.total_html_read, 12.0 / 1024.0);
+    }
+
+    #[test]
+    fn unwrap_runner_falls_back_to_a_shared_clone() {
+        let runner = Arc::new(runner(&["http://localhost:8080"]));
+        let lingering = Arc::clone(&runner); // e.g a task that didn't finish yet
+        let unwrapped = Runner::unwrap_runner(runner);
+        unwrapped.report.lock().unwrap().completed_requests = 7;
+        assert!(Arc::ptr_eq(&unwrapped.report, &lingering.report));
+        assert_eq!(lingering.report().completed_requests, 7);
+
+        let alone = Runner::unwrap_runner(Arc::new(unwrapped));
+        assert_eq!(alone.report().completed_requests, 7);
+    }
+
+    #[test]
+    fn rates_use_the_wall_clock() {
+        let started = std::time::Instant::now();
+        let report = Report {
+            duration: Duration::from_secs(3), // the timer thread's view lags behind
+            started_at: Some(started),
+            finished_at: Some(started + Duration::from_secs(4)),
+            ..Default::default()
+        };
+        assert_eq!(report.elapsed(), Duration::from_secs(4));
+        assert_eq!(report.per_sec(100.0), 25.0);
+
+        let not_started = Report { duration: Duration::from_secs(2), ..Default::default() };
+        assert_eq!(not_started.per_sec(10.0), 5.0);
+        assert_eq!(Report::default().per_sec(10.0), 0.0);
+    }
+
+    #[test]
+    fn reports_without_latencies_have_no_nan() {
+        let formats: [&[&str]; 7] = [
+            &[],
+            &["-s"],
+            &["-o", "breakdown"],
+            &["-o", "json"],
+            &["-o", "prometheus"],
+            &["-o", "openmetrics"],
+            &["-o", "markdown"],
+        ];
+        for format in formats {
+            let runner = runner(&[format, &["http://localhost:8080"]].concat());
+            runner.report.lock().unwrap().failed_requests = 5; // nothing completed, no time elapsed
+            let mut out = Vec::new();
+            runner.write_report(&mut out).unwrap();
+            let out = String::from_utf8(out).unwrap();
+            let mut words = out.split(|c: char| !c.is_ascii_alphanumeric());
+            assert!(!words.any(|word| word == "NaN" || word == "inf"), "{:?}: {}", format, out);
+        }
+    }
+
+    #[test]
+    fn records_requests_as_json_lines() {
+        let url = serve(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+        let path = std::env::temp_dir().join(format!("benchener-record-test-{}.jsonl", std::process::id()));
+        let record = path.to_str().unwrap();
+        runner(&["-q", "-n", "2", "-c", "1", "-X", "POST", "-H", "X-Token: abc", "--body", "hello", "--record", record, &url])
+            .run()
+            .unwrap();
+        let recording = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let lines: Vec<serde_json::Value> = recording.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            serde_json::json!({
+                "method": "POST",
+                "url": url,
+                "headers": [["X-Token", "abc"]],
+                "body_sha256": "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824",
+            })
+        );
+    }
+
+    #[test]
+    fn verifies_the_fingerprint_of_a_json_report() {
+        let url = serve(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+        let path = std::env::temp_dir().join(format!("benchener-verify-test-{}.json", std::process::id()));
+        let out_file = path.to_str().unwrap();
+        let mut runner = runner(&["-q", "-n", "5", "-o", "json", "--out-file", out_file, &url]);
+        runner.run().unwrap();
+        runner.print_report().unwrap();
+        let verified = ReportSummary::verify_fingerprint(out_file);
+
+        let mut summary: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        summary["completed_requests"] = serde_json::json!(6);
+        std::fs::write(&path, summary.to_string()).unwrap();
+        let tampered = ReportSummary::verify_fingerprint(out_file);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(verified.unwrap(), summary["fingerprint"].as_str().unwrap());
+        assert!(matches!(tampered, Err(VerifyError::Mismatch { .. })), "{:?}", tampered);
+    }
+
+    #[test]
+    fn restricts_the_redirects_followed() {
+        let elsewhere = serve(b"HTTP/1.1 302 Found\r\nLocation: http://example.com/\r\nContent-Length: 0\r\n\r\n");
+        let mut blocked = runner(&["-q", "-n", "3", "-c", "1", "-L", &elsewhere]);
+        let report = blocked.run().unwrap();
+        assert_eq!((report.completed_requests, report.redirect_3xx, report.blocked_redirects), (3, 3, 3));
+
+        let same_host = serve(b"HTTP/1.1 307 Temporary Redirect\r\nLocation: /again\r\nContent-Length: 0\r\n\r\n");
+        let mut looping = runner(&["-q", "-n", "2", "-c", "1", "--max-redirects", "3", &same_host]);
+        let report = looping.run().unwrap();
+        assert_eq!((report.failed_requests, report.blocked_redirects), (2, 0));
+        assert_eq!(report.errors_by_kind.get("TooManyRedirects"), Some(&2));
+    }
+
+    #[test]
+    fn redirect_hops_are_not_queue_time() {
+        let url = serve_by(|head| {
+            if head.starts_with(b"GET / ") {
+                std::thread::sleep(Duration::from_millis(100));
+                b"HTTP/1.1 302 Found\r\nLocation: /moved\r\nContent-Length: 0\r\n\r\n"
+            } else {
+                b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n"
+            }
+        });
+        let report = runner(&["-q", "-n", "3", "-c", "1", "-L", &url]).run().unwrap();
+        assert_eq!((report.completed_requests, report.non_2xx_responses), (3, 0));
+        // the slow first hop is part of the time to first byte, not of the time spent in the client
+        assert!(Report::histogram_stats(&report.ttfb_times).unwrap().min >= 100.0);
+        let queue = Report::histogram_stats(&report.queue_times).unwrap();
+        assert!(queue.max < 50.0, "{:?}", queue);
+        assert!(report.warnings.is_empty(), "{:?}", report.warnings);
+    }
+
+    #[test]
+    fn checks_every_target_before_running() {
+        let up = serve(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+        let closed = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}/", listener.local_addr().unwrap())
+        };
+        let path = std::env::temp_dir().join(format!("benchener-targets-test-{}.txt", std::process::id()));
+        std::fs::write(&path, format!("{}a\n{}b\n{}\n", up, up, closed)).unwrap();
+        let mut runner = runner(&["-q", "-n", "3", "--urls-file", path.to_str().unwrap()]);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(runner.targets(), vec![format!("{}a", up).as_str(), closed.as_str()]);
+        let err = runner.run().unwrap_err();
+        assert!(matches!(&err, RunError::Unreachable(url, _) if *url == closed), "{}", err);
+        assert_eq!(runner.report().completed_requests, 0);
+    }
+
+    // fails on output breaking the OpenMetrics text format rules the report relies on
+    fn check_openmetrics(text: &str) {
+        let body = text.strip_suffix("# EOF\n").expect("ends with # EOF");
+        let mut families: Vec<(String, String)> = Vec::new(); // (name, type), in order
+        let mut bucket = (f64::NEG_INFINITY, 0.0); // previous bound and count of the histogram
+        let mut buckets_total = None;
+        for line in body.lines() {
+            assert!(!line.is_empty() && !line.contains("# EOF"), "{:?}", line);
+            if let Some(meta) = line.strip_prefix("# ") {
+                let mut parts = meta.splitn(3, ' ');
+                let (keyword, name, rest) = (parts.next().unwrap(), parts.next().unwrap(), parts.next().unwrap());
+                match keyword {
+                    "HELP" => {
+                        assert!(families.iter().all(|(family, _)| family != name), "{} is declared twice", name);
+                        families.push((name.to_string(), String::new()));
+                    }
+                    "TYPE" => {
+                        let last = families.last_mut().filter(|(family, _)| family == name).expect("TYPE follows HELP");
+                        assert!(["counter", "gauge", "histogram"].contains(&rest), "{}", line);
+                        assert!(!(rest == "counter" && name.ends_with("_total")), "{}", line);
+                        last.1 = rest.to_string();
+                    }
+                    "UNIT" => assert!(name.ends_with(&format!("_{}", rest)), "{}", line),
+                    _ => panic!("unknown metadata {:?}", line),
+                }
+                continue;
+            }
+
+            let (sample, exemplar) = match line.split_once(" # ") {
+                Some((sample, exemplar)) => (sample, Some(exemplar)),
+                None => (line, None),
+            };
+            let (series, value) = sample.rsplit_once(' ').unwrap();
+            let value: f64 = value.parse().unwrap_or_else(|_| panic!("{:?}", line));
+            let (name, labels) = series.split_once('{').unwrap();
+            assert!(labels.ends_with('}'), "{}", line);
+            let (family, kind) = families.last().expect("sample after its metadata");
+            let suffixes: &[&str] = match kind.as_str() {
+                "counter" => &["_total"],
+                "histogram" => &["_bucket", "_sum", "_count"],
+                _ => &[""],
+            };
+            assert!(suffixes.iter().any(|suffix| *name == format!("{}{}", family, suffix)), "{} in {}", name, family);
+
+            if name.ends_with("_bucket") {
+                let bound = labels.split("le=\"").nth(1).unwrap().split('"').next().unwrap();
+                let le: f64 = if bound == "+Inf" { f64::INFINITY } else { bound.parse().unwrap() };
+                assert!(bound == "+Inf" || bound.contains('.'), "le {} is not a canonical float", bound);
+                assert!(le > bucket.0 && value >= bucket.1, "{}", line);
+                if let Some(exemplar) = exemplar {
+                    let (exemplar_labels, rest) = exemplar.split_once("} ").unwrap();
+                    assert!(exemplar_labels.starts_with("{request_id=\"") && exemplar_labels.len() <= 128, "{}", line);
+                    let mut numbers = rest.split(' ').map(|number| number.parse::<f64>().unwrap());
+                    let (latency, timestamp) = (numbers.next().unwrap(), numbers.next().unwrap());
+                    assert!(latency > bucket.0 && latency <= le && timestamp > 1e9, "{}", line);
+                }
+                bucket = (le, value);
+                if le.is_infinite() {
+                    buckets_total = Some(value);
+                }
+            } else {
+                assert!(exemplar.is_none(), "exemplar outside a bucket: {}", line);
+            }
+            if name.ends_with("_count") {
+                assert_eq!(buckets_total, Some(value), "+Inf bucket and count differ");
+            }
         }
     }
+
+    #[test]
+    fn openmetrics_report_links_buckets_to_request_ids() {
+        // requests without an id are refused, so every completed one carried it
+        let url = serve_by(|head| {
+            if String::from_utf8_lossy(head).to_ascii_lowercase().contains("\r\nx-request-id: ") {
+                b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n"
+            } else {
+                b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n"
+            }
+        });
+        let mut runner = runner(&["-q", "-n", "20", "-c", "2", "-o", "openmetrics", "--seed", "7", "--name", "a \"b\"", &url]);
+        let report = runner.run().unwrap();
+        assert_eq!((report.completed_requests, report.non_2xx_responses), (20, 0));
+
+        let mut out = Vec::new();
+        runner.write_report(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        check_openmetrics(&out);
+        assert!(out.contains(" # {request_id=\"0000000000000007-"), "{}", out);
+        assert!(out.contains("# UNIT benchener_request_duration_seconds seconds\n"));
+    }
+
+    #[test]
+    fn warns_about_a_low_fd_limit() {
+        let runner = runner(&["-n", "1000", "-c", "100", "http://localhost:8080"]);
+        assert_eq!(runner.record_fd_limit(100_000), None);
+        let warning = runner.record_fd_limit(120).unwrap();
+        assert!(warning.starts_with("open file limit (120) is too low for 100 connections"), "{}", warning);
+        let report = runner.report();
+        assert_eq!((report.fd_limit, report.warnings), (Some(120), vec![warning]));
+    }
 }